@@ -5,6 +5,7 @@ use anyhow::{Context, Result};
 use log::{debug, error, info, trace, warn};
 use tokio::task::JoinHandle;
 
+use crate::config::SharedConfig;
 use crate::messages::{Request, State};
 use crate::model::{RequestSender, StateReceiver};
 use crate::track::Track;
@@ -72,7 +73,9 @@ impl FetchRequest {
                         self.completed = false;
                         error!("Error during in-flight request for track {e:#} after {task_elapsed_secs}s");
                     }
-                    Ok(Ok(_)) => {
+                    Ok(Ok(track)) => {
+                        // The fetched copy knows which audio quality it got
+                        self.track = track;
                         self.completed = self.track.cached();
                         self.failed = !self.completed;
                         info!("In-flight request for track completed (successful: {} retries: {}) after {task_elapsed_secs}s", &self.completed, &self.retry_count);
@@ -144,7 +147,7 @@ impl FetchRequest {
             self.completed = true;
         } else {
             info!("Cache miss {}", &self.track.title);
-            let track = self.track.clone();
+            let mut track = self.track.clone();
             let th = tokio::spawn(async move {
                 //trace!("Retrieving track {}...", &track.title);
                 track.download_to_cache(&client).await?;
@@ -176,16 +179,26 @@ pub(crate) struct TrackCacher {
 }
 
 impl TrackCacher {
-    pub(crate) fn new(state_receiver: StateReceiver, request_sender: RequestSender) -> Self {
-        TrackCacher {
-            client: reqwest::Client::new(),
+    pub(crate) fn new(
+        config: SharedConfig,
+        state_receiver: StateReceiver,
+        request_sender: RequestSender,
+    ) -> Result<Self> {
+        let client = crate::network::http_client(
+            config
+                .read()
+                .expect("config read for network settings")
+                .network(),
+        )?;
+        Ok(TrackCacher {
+            client,
             active_requests: Vec::with_capacity(MAX_ACTIVE_FETCHES),
             pending_tracks: VecDeque::with_capacity(8),
             station_id: None,
             request_sender,
             state_receiver,
             dirty: false,
-        }
+        })
     }
 
     fn publish_request(&mut self, request: Request) -> Result<()> {
@@ -368,3 +381,56 @@ impl TrackCacher {
         Ok(dirty)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AudioQuality;
+    use crate::pandora::mock::MockPandora;
+
+    #[tokio::test]
+    async fn fetch_falls_back_to_available_audio_quality() {
+        let mock = MockPandora::start().await;
+        mock.set_high_quality_missing(true);
+        let cache_dir = std::env::temp_dir().join(format!(
+            "{}-fetch-fallback-{}",
+            clap::crate_name!(),
+            std::process::id()
+        ));
+        let track = mock.track("101", &cache_dir);
+        assert_eq!(track.audio_streams[0].0, AudioQuality::High);
+
+        let (state_tx, state_rx) = async_broadcast::broadcast(8);
+        let (request_tx, mut request_rx) = tokio::sync::mpsc::channel(8);
+        let mut cacher =
+            TrackCacher::new(mock.config(), state_rx, request_tx).expect("track cacher");
+        state_tx
+            .broadcast(State::Tuned(String::from("101")))
+            .await
+            .expect("state broadcast");
+        state_tx
+            .broadcast(State::TrackCaching(track))
+            .await
+            .expect("state broadcast");
+
+        let request = tokio::time::timeout(std::time::Duration::from_secs(10), async {
+            loop {
+                cacher.update().await.expect("track cacher update");
+                if let Ok(request) = request_rx.try_recv() {
+                    return request;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("timed out waiting for track fetch");
+        let _ = std::fs::remove_dir_all(&cache_dir);
+
+        match request {
+            Request::AddTrack(track) => {
+                assert_eq!(track.audio_quality, Some(AudioQuality::Medium));
+            }
+            other => panic!("unexpected request {other:?}"),
+        }
+    }
+}
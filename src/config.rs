@@ -39,6 +39,121 @@ impl Default for CachePolicy {
     }
 }
 
+/// Preferred quality of the audio streams downloaded for playback.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub(crate) enum AudioQuality {
+    Low,
+    Medium,
+    High,
+}
+
+impl AudioQuality {
+    /// The order in which to try each quality when this one is preferred: this
+    /// one first, then the next best, with anything lower coming last.
+    pub(crate) fn fallback_order(self) -> [Self; 3] {
+        match self {
+            Self::Low => [Self::Low, Self::Medium, Self::High],
+            Self::Medium => [Self::Medium, Self::High, Self::Low],
+            Self::High => [Self::High, Self::Medium, Self::Low],
+        }
+    }
+}
+
+impl Default for AudioQuality {
+    fn default() -> Self {
+        Self::High
+    }
+}
+
+impl std::fmt::Display for AudioQuality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Low => write!(f, "low"),
+            Self::Medium => write!(f, "medium"),
+            Self::High => write!(f, "high"),
+        }
+    }
+}
+
+/// The partner (application) credentials and endpoint used for the Pandora
+/// session. Any fields left out of the config file take the "android" partner
+/// values.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub(crate) struct PartnerProfile {
+    pub(crate) endpoint: String,
+    pub(crate) username: String,
+    pub(crate) password: String,
+    pub(crate) device_model: String,
+    pub(crate) version: Option<String>,
+    pub(crate) encrypt_key: String,
+    pub(crate) decrypt_key: String,
+}
+
+impl PartnerProfile {
+    pub(crate) fn android() -> Self {
+        Self {
+            endpoint: String::from("https://tuner.pandora.com/services/json"),
+            username: String::from("android"),
+            password: String::from("AC7IBG09A3DTSYM4R41UJWL07VLN8JI7"),
+            device_model: String::from("android-generic"),
+            version: Some(String::from("5")),
+            encrypt_key: String::from("6#26FRL$ZWD"),
+            decrypt_key: String::from("R=U!LH$O2B#"),
+        }
+    }
+}
+
+impl Default for PartnerProfile {
+    fn default() -> Self {
+        Self::android()
+    }
+}
+
+/// How to reach the network, for both Pandora API requests and track
+/// downloads.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub(crate) struct NetworkSettings {
+    /// Proxy for all requests, e.g. "http://proxy:3128" or "socks5://proxy:1080"
+    pub(crate) proxy: Option<String>,
+    /// Comma-separated hosts and domains that bypass the proxy
+    pub(crate) no_proxy: Option<String>,
+    /// PEM files with extra root certificates to trust
+    pub(crate) ca_certificates: Vec<PathBuf>,
+    /// Seconds to wait for a connection to be established, if limited
+    pub(crate) connect_timeout_secs: Option<u64>,
+    /// Seconds to wait between reads of a response, if limited
+    pub(crate) read_timeout_secs: Option<u64>,
+}
+
+/// Whether to even out loudness between tracks using the ReplayGain
+/// adjustment Pandora sends with each track.  Off unless enabled in the
+/// config file.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default)]
+pub(crate) struct ReplayGainSettings {
+    pub(crate) enabled: bool,
+    /// Added to every track's adjustment, in dB, limited to
+    /// +/-`MAX_PREAMP_DB`
+    pub(crate) preamp_db: f32,
+}
+
+/// The largest pre-amp, either way, that is applied
+const MAX_PREAMP_DB: f32 = 15.0;
+
+impl ReplayGainSettings {
+    /// The pre-amp to apply, ignoring values that aren't a number and
+    /// limiting the rest to a sane range.
+    pub(crate) fn preamp_db(&self) -> f32 {
+        if self.preamp_db.is_finite() {
+            self.preamp_db.clamp(-MAX_PREAMP_DB, MAX_PREAMP_DB)
+        } else {
+            0.0
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub(crate) enum Credentials {
     Keyring(String),
@@ -224,6 +339,12 @@ impl std::cmp::PartialEq<Credentials> for Credentials {
     }
 }
 
+/// Settings left at their defaults aren't written to the config file, so that
+/// changes to the defaults still reach everyone who hasn't overridden them.
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
 /// Deserializes `login` from JSON: `null` is treated as Session(None, None)
 /// so that config files written after "Don't Store" never crash on reload.
 fn deserialize_login_opt<'de, D>(d: D) -> Result<Option<Credentials>, D::Error>
@@ -244,6 +365,10 @@ pub(crate) struct PartialConfig {
     pub(crate) station_id: Option<Option<String>>,
     pub(crate) save_station: Option<bool>,
     pub(crate) volume: Option<f32>,
+    pub(crate) audio_quality: Option<AudioQuality>,
+    pub(crate) partner: Option<PartnerProfile>,
+    pub(crate) network: Option<NetworkSettings>,
+    pub(crate) replay_gain: Option<ReplayGainSettings>,
 }
 
 impl PartialConfig {
@@ -314,6 +439,13 @@ pub(crate) struct Config {
     pub(crate) station_id: Option<String>,
     pub(crate) save_station: bool,
     pub(crate) volume: f32,
+    pub(crate) audio_quality: AudioQuality,
+    #[serde(skip_serializing_if = "is_default")]
+    pub(crate) partner: PartnerProfile,
+    #[serde(skip_serializing_if = "is_default")]
+    pub(crate) network: NetworkSettings,
+    #[serde(skip_serializing_if = "is_default")]
+    pub(crate) replay_gain: ReplayGainSettings,
 }
 
 impl std::default::Default for Config {
@@ -326,6 +458,10 @@ impl std::default::Default for Config {
             save_station: true,
             path: None,
             volume: 1.0f32,
+            audio_quality: AudioQuality::default(),
+            partner: PartnerProfile::default(),
+            network: NetworkSettings::default(),
+            replay_gain: ReplayGainSettings::default(),
         }
     }
 }
@@ -440,6 +576,34 @@ impl Config {
                 self.volume = volume;
             }
         }
+
+        if let Some(audio_quality) = other.audio_quality {
+            if self.audio_quality != audio_quality {
+                self.dirty |= true;
+                self.audio_quality = audio_quality;
+            }
+        }
+
+        if let Some(partner) = &other.partner {
+            if self.partner != *partner {
+                self.dirty |= true;
+                self.partner = partner.clone();
+            }
+        }
+
+        if let Some(network) = &other.network {
+            if self.network != *network {
+                self.dirty |= true;
+                self.network = network.clone();
+            }
+        }
+
+        if let Some(replay_gain) = &other.replay_gain {
+            if self.replay_gain != *replay_gain {
+                self.dirty |= true;
+                self.replay_gain = replay_gain.clone();
+            }
+        }
         debug!("Settings after update: {self:?}");
     }
 
@@ -458,4 +622,44 @@ impl Config {
     pub(crate) fn volume(&self) -> f32 {
         self.volume
     }
+
+    pub(crate) fn audio_quality(&self) -> AudioQuality {
+        self.audio_quality
+    }
+
+    pub(crate) fn partner_profile(&self) -> &PartnerProfile {
+        &self.partner
+    }
+
+    pub(crate) fn network(&self) -> &NetworkSettings {
+        &self.network
+    }
+
+    pub(crate) fn replay_gain(&self) -> &ReplayGainSettings {
+        &self.replay_gain
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_settings_are_not_written() {
+        let value = serde_json::to_value(Config::default()).expect("serialize config");
+        for key in ["partner", "network", "replay_gain"] {
+            assert!(value.get(key).is_none(), "{key} written with its defaults");
+        }
+
+        let mut config = Config::default();
+        config.update_from(&PartialConfig {
+            replay_gain: Some(ReplayGainSettings {
+                enabled: true,
+                preamp_db: 0.0,
+            }),
+            ..PartialConfig::default()
+        });
+        let value = serde_json::to_value(&config).expect("serialize config");
+        assert!(value.get("replay_gain").is_some());
+    }
 }
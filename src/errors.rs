@@ -14,8 +14,24 @@ pub(crate) enum Error {
     InvalidOperationForState(String, String),
     #[error("Requested track not in cache ({0})")]
     TrackNotCached(String),
+    #[error("No audio stream available for track ({0})")]
+    NoAudioStream(String),
     #[error("Requested station {0} not in the station list")]
     InvalidStation(String),
+    #[error("Pandora rejected the account login: {0}")]
+    PandoraAuthFailure(String),
+    #[error("Pandora is limiting requests: {0}")]
+    PandoraRateLimited(String),
+    #[error("Station {0} no longer exists on Pandora")]
+    PandoraStationUnavailable(String),
+    #[error("Added shared station {0}, but it can't be edited: {1}")]
+    PandoraSharedStationNotTransformed(String, String),
+    #[error("Pandora service unavailable: {0}")]
+    PandoraUnavailable(String),
+    #[error("Network failure reaching Pandora: {0}")]
+    PandoraNetworkFailure(String),
+    #[error("Pandora request failed: {0}")]
+    PandoraRequestFailure(String),
 }
 
 /*
@@ -33,4 +49,15 @@ impl Error {
     pub(crate) fn missing_auth_token(&self) -> bool {
         matches!(self, Error::PanharmoniconMissingAuthToken)
     }
+
+    /// Whether this is a Pandora failure that should clear up by itself, so
+    /// that the request is worth trying again after a while.
+    pub(crate) fn pandora_transient(&self) -> bool {
+        matches!(
+            self,
+            Error::PandoraRateLimited(_)
+                | Error::PandoraUnavailable(_)
+                | Error::PandoraNetworkFailure(_)
+        )
+    }
 }
@@ -18,6 +18,7 @@ mod messages;
 mod model;
 #[cfg(feature = "mpris_server")]
 mod mpris_ui;
+mod network;
 mod pandora;
 mod player;
 #[cfg(feature = "term_ui")]
@@ -42,6 +43,13 @@ fn main() -> Result<()> {
                     config_file.to_string_lossy()
                 )),
         )
+        .arg(
+            clap::Arg::new("usage")
+                .short('u')
+                .long("usage")
+                .action(clap::ArgAction::SetTrue)
+                .help("Print the account's monthly listening hours and limit, then exit"),
+        )
         .arg(
             clap::Arg::new("debug")
                 .short('g')
@@ -132,6 +140,17 @@ fn main() -> Result<()> {
     debug!("Configuration settings: {:?}", &conf);
     let shared_config: SharedConfig = Arc::new(RwLock::new(conf));
 
+    if matches.get_flag("usage") {
+        trace!("Fetching account usage info");
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .context("Failed to build tokio runtime")?;
+        let usage = rt.block_on(pandora::fetch_usage_info(shared_config))?;
+        println!("{usage}");
+        return Ok(());
+    }
+
     trace!("Initializing Pandora API task");
     let (pandora_cmd_tx, pandora_cmd_rx) = tokio::sync::mpsc::channel(32);
     let (pandora_result_tx, pandora_result_rx) = tokio::sync::mpsc::channel(32);
@@ -140,7 +159,12 @@ fn main() -> Result<()> {
     let model = model::Model::new(shared_config.clone(), pandora_cmd_tx, pandora_result_rx);
 
     trace!("Initializing track fetcher");
-    let mut fetcher = caching::TrackCacher::new(model.updates_channel(), model.request_channel());
+    let mut fetcher = caching::TrackCacher::new(
+        shared_config.clone(),
+        model.updates_channel(),
+        model.request_channel(),
+    )
+    .context("Failed to initialize track fetcher")?;
 
     #[cfg(all(feature = "term_ui", feature = "mpris_server"))]
     let use_terminal_ui = matches.get_flag("terminal");
@@ -148,7 +172,11 @@ fn main() -> Result<()> {
     let use_terminal_ui = true;
 
     trace!("Initializing player interface");
-    let mut player = player::Player::new(model.updates_channel(), model.request_channel());
+    let mut player = player::Player::new(
+        shared_config.clone(),
+        model.updates_channel(),
+        model.request_channel(),
+    );
 
     // Polling interval for main loop and worker tasks. ~50ms keeps UI/control latency low without extra CPU.
     let naptime = Duration::from_millis(50);
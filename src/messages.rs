@@ -1,3 +1,7 @@
+use crate::pandora::{
+    GenreCategory, MusicMatch, QuickMix, SearchResults, Station, StationDetails,
+    StationRatedTracksData, UsageInfo,
+};
 use crate::track::Track;
 
 #[derive(Debug, Clone)]
@@ -29,6 +33,59 @@ pub(crate) enum Request {
     AddArtistSeed,
     /// Remove a seed by id (from station seeds; UI looks up id for current track/artist).
     RemoveSeed(String),
+    /// Add an artist, song or genre as a seed on the tuned station (music_token from search).
+    AddSeed(String),
+    /// Search for artists, songs and genres matching free text.
+    Search(String),
+    /// Create a new station from the currently playing song, and tune to it.
+    CreateTrackStation,
+    /// Create a new station from the currently playing artist, and tune to it.
+    CreateArtistStation,
+    /// Rename a station.
+    RenameStation {
+        station_id: String,
+        station_name: String,
+    },
+    /// Delete a station by id.
+    DeleteStation(String),
+    /// Create a station from a music token (from search results), optionally tuning to it.
+    CreateStation {
+        music_token: String,
+        tune: bool,
+    },
+    /// Get the genre station categories, for creating a station from a genre.
+    GetGenreStations,
+    /// Explain why the currently playing track was chosen.
+    ExplainTrack,
+    /// Set which stations the QuickMix (Shuffle) station plays from.
+    SetQuickMix(Vec<String>),
+    /// Shelve the currently playing song for a month ("tired of this song"), and skip it.
+    SleepTrack,
+    /// Bookmark the currently playing song.
+    BookmarkSong,
+    /// Bookmark the currently playing artist.
+    BookmarkArtist,
+    /// List bookmarked artists and songs.
+    GetBookmarks,
+    /// Get the account's monthly listening hours and cap.
+    GetUsageInfo,
+    /// Share the tuned station with the given email addresses.
+    ShareStation(Vec<String>),
+    /// Add a station shared by another user, from a share link or token, optionally tuning to it.
+    AcceptSharedStation {
+        shared: String,
+        tune: bool,
+    },
+    /// List the thumbs-up and thumbs-down tracks for a station.
+    ListRatedTracks(String),
+    /// Get a station's attributes, seeds and rating totals, for its info panel.
+    GetStationDetails(String),
+    /// Remove a thumbs-up or thumbs-down by feedback id (from rated tracks).
+    DeleteFeedback {
+        station_id: String,
+        feedback_id: String,
+        music_token: String,
+    },
     Quit,
 }
 
@@ -54,6 +111,50 @@ impl PartialEq<Request> for Request {
             (Request::AddTrackSeed, Request::AddTrackSeed) => true,
             (Request::AddArtistSeed, Request::AddArtistSeed) => true,
             (Request::RemoveSeed(a), Request::RemoveSeed(b)) => a == b,
+            (Request::AddSeed(a), Request::AddSeed(b)) => a == b,
+            (Request::Search(a), Request::Search(b)) => a == b,
+            (Request::CreateTrackStation, Request::CreateTrackStation) => true,
+            (
+                Request::RenameStation {
+                    station_id: a,
+                    station_name: x,
+                },
+                Request::RenameStation {
+                    station_id: b,
+                    station_name: y,
+                },
+            ) => a == b && x == y,
+            (Request::DeleteStation(a), Request::DeleteStation(b)) => a == b,
+            (Request::CreateArtistStation, Request::CreateArtistStation) => true,
+            (
+                Request::CreateStation {
+                    music_token: a,
+                    tune: x,
+                },
+                Request::CreateStation {
+                    music_token: b,
+                    tune: y,
+                },
+            ) => a == b && x == y,
+            (Request::GetGenreStations, Request::GetGenreStations) => true,
+            (Request::ExplainTrack, Request::ExplainTrack) => true,
+            (Request::SetQuickMix(a), Request::SetQuickMix(b)) => a == b,
+            (Request::SleepTrack, Request::SleepTrack) => true,
+            (Request::BookmarkSong, Request::BookmarkSong) => true,
+            (Request::BookmarkArtist, Request::BookmarkArtist) => true,
+            (Request::GetBookmarks, Request::GetBookmarks) => true,
+            (Request::GetUsageInfo, Request::GetUsageInfo) => true,
+            (Request::ShareStation(a), Request::ShareStation(b)) => a == b,
+            (
+                Request::AcceptSharedStation { shared: a, tune: x },
+                Request::AcceptSharedStation { shared: b, tune: y },
+            ) => a == b && x == y,
+            (Request::ListRatedTracks(a), Request::ListRatedTracks(b)) => a == b,
+            (Request::GetStationDetails(a), Request::GetStationDetails(b)) => a == b,
+            (
+                Request::DeleteFeedback { feedback_id: a, .. },
+                Request::DeleteFeedback { feedback_id: b, .. },
+            ) => a == b,
             _ => false,
         }
     }
@@ -94,17 +195,51 @@ pub(crate) struct StationSeedsForUi {
     pub song_seeds: Vec<(String, String)>,
     /// (artist_name, seed_id) for removing an artist seed.
     pub artist_seeds: Vec<(String, String)>,
+    /// (description, seed_id) for every artist, song and genre seed, for the seed manager.
+    pub seed_list: Vec<(String, String)>,
 }
 
 #[derive(Debug, Clone)]
 pub(crate) enum State {
     AuthFailed(String),
+    /// The config file's settings for reaching Pandora can't be used (description
+    /// of the problem); no login is attempted until they're changed.
+    ConfigError(String),
+    /// A Pandora request failed (description of the failure, for the user).
+    PandoraError(String),
+    /// A failed Pandora request will be tried again (what it was for, delay before it is).
+    Retrying(String, std::time::Duration),
     Connected,
     Disconnected,
-    AddStation(String, String),
+    /// A station was added to the station list.
+    AddStation(Station),
+    /// A station was deleted (station id).
+    RemoveStation(String),
+    /// A station was renamed (new name, station id).
+    RenameStation(String, String),
     Tuned(String),
+    /// The QuickMix (Shuffle) station, and the stations it plays from.
+    QuickMix(QuickMix),
     /// Seeds for a station (song music_tokens and artist names) so UI can show seed indicator.
     StationSeeds(StationSeedsForUi),
+    /// Matches for a `Request::Search`.
+    SearchResults(SearchResults),
+    /// Account listening hours and cap (refreshed periodically while connected).
+    UsageInfo(UsageInfo),
+    /// Bookmarked artists and songs for a `Request::GetBookmarks`.
+    Bookmarks(Vec<MusicMatch>),
+    /// Genre station categories for a `Request::GetGenreStations`.
+    GenreStations(Vec<GenreCategory>),
+    /// Music Genome traits explaining why a track was chosen (track token, traits).
+    TrackExplanation(String, Vec<String>),
+    /// Rated tracks for a station (station id, tracks).
+    RatedTracks(String, StationRatedTracksData),
+    /// Attributes, seeds and rating totals for a `Request::GetStationDetails`.
+    StationDetails(StationDetails),
+    /// A `Request::GetStationDetails` failed (station id, description of the failure).
+    StationDetailsFailed(String, String),
+    /// A thumbs-up or thumbs-down was removed (feedback id).
+    FeedbackDeleted(String),
     Buffering,
     TrackCaching(Track),
     TrackStarting(Track),
@@ -122,11 +257,28 @@ pub(crate) enum State {
 impl PartialEq<State> for State {
     fn eq(&self, other: &State) -> bool {
         match (self, other) {
+            (State::ConfigError(a), State::ConfigError(b)) => a == b,
+            (State::PandoraError(a), State::PandoraError(b)) => a == b,
+            (State::Retrying(a, x), State::Retrying(b, y)) => a == b && x == y,
             (State::Connected, State::Connected) => true,
             (State::Disconnected, State::Disconnected) => true,
-            (State::AddStation(a, x), State::AddStation(b, y)) => a == b && x == y,
+            (State::AddStation(a), State::AddStation(b)) => a == b,
+            (State::RemoveStation(a), State::RemoveStation(b)) => a == b,
+            (State::RenameStation(a, x), State::RenameStation(b, y)) => a == b && x == y,
             (State::Tuned(a), State::Tuned(b)) => a == b,
+            (State::QuickMix(a), State::QuickMix(b)) => a == b,
             (State::StationSeeds(a), State::StationSeeds(b)) => a.station_id == b.station_id,
+            (State::SearchResults(a), State::SearchResults(b)) => a.query == b.query,
+            (State::UsageInfo(a), State::UsageInfo(b)) => a == b,
+            (State::Bookmarks(a), State::Bookmarks(b)) => a == b,
+            (State::GenreStations(a), State::GenreStations(b)) => a == b,
+            (State::TrackExplanation(a, _), State::TrackExplanation(b, _)) => a == b,
+            (State::RatedTracks(a, _), State::RatedTracks(b, _)) => a == b,
+            (State::StationDetails(a), State::StationDetails(b)) => {
+                a.station.station_id == b.station.station_id
+            }
+            (State::StationDetailsFailed(a, _), State::StationDetailsFailed(b, _)) => a == b,
+            (State::FeedbackDeleted(a), State::FeedbackDeleted(b)) => a == b,
             (State::TrackStarting(t), State::TrackStarting(u)) => t.track_token == u.track_token,
             (State::Next(Some(t)), State::Next(Some(u))) => t.track_token == u.track_token,
             (State::Next(None), State::Next(None)) => true,
@@ -6,10 +6,13 @@ use either::Either;
 use log::{debug, error, info, trace, warn};
 use tokio::sync::mpsc;
 
-use crate::config::{PartialConfig, SharedConfig};
+use crate::config::{NetworkSettings, PartialConfig, SharedConfig};
 use crate::errors::Error;
 use crate::messages::{Request, State, StopReason};
-use crate::pandora::{PandoraCommand, PandoraResult};
+use crate::pandora::{
+    shared_station_token, CommandId, PandoraCommand, PandoraResult, QuickMix, Station,
+    TaggedCommand, TaggedResult,
+};
 use crate::track::Track;
 
 pub(crate) type StateSender = async_broadcast::Sender<State>;
@@ -31,6 +34,7 @@ const PLAYLIST_MAX_LEN: usize = 12;
 // pandora/connected: bool
 // pandora/station: Option<(String, String)>
 // pandora/stations: HashMap<String, String>
+// pandora/quickmix: QuickMix
 // pandora/readylist: VecDeque<Track>
 // pandora/fetchlist: Vec<Track>
 // panharmonicon/quitting: bool
@@ -44,11 +48,23 @@ pub(crate) struct Model {
     player_progress: Option<Duration>,
     player_length: Option<Duration>,
     session_connected: bool,
-    pending_connect: bool,
-    pending_station_list: bool,
-    pending_playlist: bool,
+    /// Network settings the Pandora task couldn't use, so that logging in
+    /// waits until they're changed.
+    invalid_network: Option<NetworkSettings>,
+    /// Ids of the in-flight commands whose results the model is waiting on.
+    pending_connect: Option<CommandId>,
+    pending_station_list: Option<CommandId>,
+    pending_playlist: Option<CommandId>,
+    pending_station_details: Option<CommandId>,
+    /// Id of the last command sent to the Pandora task.
+    last_command_id: CommandId,
+    /// Station to tune to once it shows up in the station list (e.g. one we just created).
+    pending_tune: Option<String>,
+    /// Whether to tune to the next station created at our request.
+    tune_created_station: bool,
     pandora_station: Option<(String, String)>,
-    pandora_stations: HashMap<String, String>,
+    pandora_stations: HashMap<String, Station>,
+    pandora_quickmix: QuickMix,
     pandora_readylist: VecDeque<Track>,
     pandora_fetchlist: Vec<Track>,
     panharmonicon_quitting: bool,
@@ -56,8 +72,8 @@ pub(crate) struct Model {
     request_receiver: RequestReceiver,
     state_sender: StateSender,
     state_receiver: StateReceiver,
-    pandora_cmd_tx: mpsc::Sender<PandoraCommand>,
-    pandora_result_rx: mpsc::Receiver<PandoraResult>,
+    pandora_cmd_tx: mpsc::Sender<TaggedCommand>,
+    pandora_result_rx: mpsc::Receiver<TaggedResult>,
     config: SharedConfig,
     dirty: bool,
 }
@@ -65,8 +81,8 @@ pub(crate) struct Model {
 impl Model {
     pub(crate) fn new(
         config: SharedConfig,
-        pandora_cmd_tx: mpsc::Sender<PandoraCommand>,
-        pandora_result_rx: mpsc::Receiver<PandoraResult>,
+        pandora_cmd_tx: mpsc::Sender<TaggedCommand>,
+        pandora_result_rx: mpsc::Receiver<TaggedResult>,
     ) -> Self {
         let (request_sender, request_receiver) = mpsc::channel(REQUEST_CHANNEL_CAP);
         let (state_sender, state_receiver) = async_broadcast::broadcast(64);
@@ -79,11 +95,17 @@ impl Model {
             player_progress: None,
             player_length: None,
             session_connected: false,
-            pending_connect: false,
-            pending_station_list: false,
-            pending_playlist: false,
+            invalid_network: None,
+            pending_connect: None,
+            pending_station_list: None,
+            pending_playlist: None,
+            pending_station_details: None,
+            last_command_id: 0,
+            pending_tune: None,
+            tune_created_station: false,
             pandora_station: None,
             pandora_stations: HashMap::with_capacity(16),
+            pandora_quickmix: QuickMix::default(),
             pandora_readylist: VecDeque::with_capacity(PLAYLIST_MAX_LEN),
             pandora_fetchlist: Vec::with_capacity(FETCHLIST_MAX_LEN),
             panharmonicon_quitting: false,
@@ -108,6 +130,17 @@ impl Model {
         self.state_receiver.clone()
     }
 
+    /// Send a command to the Pandora task, returning the id its result will carry.
+    async fn send_command(&mut self, command: PandoraCommand) -> CommandId {
+        self.last_command_id += 1;
+        let id = self.last_command_id;
+        let _ = self
+            .pandora_cmd_tx
+            .send(TaggedCommand { id, command })
+            .await;
+        id
+    }
+
     async fn publish_state(&mut self, state: State) -> Result<()> {
         debug!("State update: {state:?}");
         self.state_sender.broadcast(state).await?;
@@ -119,14 +152,26 @@ impl Model {
             info!("Connect request ignored. Already connected.");
             return Ok(());
         }
-        if self.pending_connect {
+        if self.pending_connect.is_some() {
             trace!("Connect already in progress.");
             return Ok(());
         }
+        if let Some(invalid) = &self.invalid_network {
+            let network = self
+                .config
+                .read()
+                .expect("config read for network settings")
+                .network()
+                .clone();
+            if *invalid == network {
+                trace!("Not connecting until the network settings are fixed.");
+                return Ok(());
+            }
+            self.invalid_network = None;
+        }
         trace!("Attempting pandora login...");
         self.dirty |= true;
-        self.pending_connect = true;
-        let _ = self.pandora_cmd_tx.send(PandoraCommand::Connect).await;
+        self.pending_connect = Some(self.send_command(PandoraCommand::Connect).await);
         Ok(())
     }
 
@@ -136,8 +181,12 @@ impl Model {
 
     pub(crate) async fn disconnect(&mut self) -> Result<()> {
         self.session_connected = false;
-        self.pending_connect = false;
-        let _ = self.pandora_cmd_tx.send(PandoraCommand::Disconnect).await;
+        // The task cancels the commands still running when it disconnects, so
+        // there's no sense waiting on them until it says so
+        self.pending_connect = None;
+        self.pending_station_list = None;
+        self.pending_playlist = None;
+        self.send_command(PandoraCommand::Disconnect).await;
         self.clear_stations().await?;
         self.dirty |= true;
         self.publish_state(State::Disconnected).await?;
@@ -146,6 +195,7 @@ impl Model {
 
     pub(crate) async fn clear_stations(&mut self) -> Result<()> {
         self.pandora_stations.clear();
+        self.pandora_quickmix = QuickMix::default();
         self.untune().await
     }
 
@@ -158,7 +208,11 @@ impl Model {
         }
 
         if self.tuned().as_deref() != Some(station_id) {
-            if let Some(name) = self.pandora_stations.get(station_id).map(|s| s.to_string()) {
+            if let Some(name) = self
+                .pandora_stations
+                .get(station_id)
+                .map(|s| s.station_name.clone())
+            {
                 info!("Switched station to {name} ({station_id})");
                 self.untune().await?;
                 self.pandora_station = Some((station_id.to_string(), name.to_string()));
@@ -166,9 +220,7 @@ impl Model {
                 trace!("send notification 'tuned'");
                 self.publish_state(State::Tuned(station_id.to_string()))
                     .await?;
-                let _ = self
-                    .pandora_cmd_tx
-                    .send(PandoraCommand::ListSeeds(station_id.to_string()))
+                self.send_command(PandoraCommand::ListSeeds(station_id.to_string()))
                     .await;
                 trace!("Updating station in config");
                 self.config
@@ -194,6 +246,8 @@ impl Model {
             .update_from(&PartialConfig::default().station(None));
 
         self.clear_playlist();
+        // Any playlist still on its way is for this station, and will be discarded
+        self.pending_playlist = None;
         if self.get_playing().is_some() {
             self.stop(StopReason::Untuning).await?;
         }
@@ -259,7 +313,7 @@ impl Model {
         info!("Application request to quit");
         self.panharmonicon_quitting = true;
         self.dirty |= true;
-        let _ = self.pandora_cmd_tx.send(PandoraCommand::Quit).await;
+        self.send_command(PandoraCommand::Quit).await;
         trace!("send notification 'quit'");
         self.publish_state(State::Quit).await?;
         Ok(())
@@ -299,9 +353,7 @@ impl Model {
                 "Disconnected"
             )));
         }
-        let _ = self
-            .pandora_cmd_tx
-            .send(PandoraCommand::RateTrack(track, rating))
+        self.send_command(PandoraCommand::RateTrack(track, rating))
             .await;
         // Local state and notify_playing will be updated when we receive PandoraResult::Rated
         self.dirty |= true;
@@ -327,13 +379,11 @@ impl Model {
                 "Disconnected"
             )));
         }
-        let _ = self
-            .pandora_cmd_tx
-            .send(PandoraCommand::AddSeed {
-                station_id,
-                music_token: track.music_id,
-            })
-            .await;
+        self.send_command(PandoraCommand::AddSeed {
+            station_id,
+            music_token: track.music_id,
+        })
+        .await;
         Ok(())
     }
 
@@ -356,13 +406,32 @@ impl Model {
                 "Disconnected"
             )));
         }
-        let _ = self
-            .pandora_cmd_tx
-            .send(PandoraCommand::AddArtistSeed {
-                station_id,
-                artist_name: track.artist_name,
-            })
-            .await;
+        self.send_command(PandoraCommand::AddArtistSeed {
+            station_id,
+            artist_name: track.artist_name,
+        })
+        .await;
+        Ok(())
+    }
+
+    async fn add_seed(&mut self, music_token: &str) -> Result<()> {
+        let station_id = self.tuned().ok_or_else(|| {
+            anyhow::anyhow!(Error::invalid_operation_for_state(
+                "add_seed",
+                "Not tuned to a station"
+            ))
+        })?;
+        if !self.connected() {
+            return Err(anyhow::anyhow!(Error::invalid_operation_for_state(
+                "add_seed",
+                "Disconnected"
+            )));
+        }
+        self.send_command(PandoraCommand::AddSeed {
+            station_id,
+            music_token: music_token.to_string(),
+        })
+        .await;
         Ok(())
     }
 
@@ -373,27 +442,388 @@ impl Model {
                 "Disconnected"
             )));
         }
-        let _ = self
-            .pandora_cmd_tx
-            .send(PandoraCommand::RemoveSeed(seed_id.to_string()))
+        self.send_command(PandoraCommand::RemoveSeed(seed_id.to_string()))
+            .await;
+        Ok(())
+    }
+
+    async fn create_station(&mut self, music_token: &str, tune: bool) -> Result<()> {
+        if !self.connected() {
+            return Err(anyhow::anyhow!(Error::invalid_operation_for_state(
+                "create_station",
+                "Disconnected"
+            )));
+        }
+        self.tune_created_station = tune;
+        self.send_command(PandoraCommand::CreateStationFromMusic {
+            music_token: music_token.to_string(),
+        })
+        .await;
+        Ok(())
+    }
+
+    async fn create_station_from_playing(&mut self, as_artist: bool) -> Result<()> {
+        let track = self.get_playing().cloned().ok_or_else(|| {
+            anyhow::anyhow!(Error::invalid_operation_for_state(
+                "create_station_from_playing",
+                "No track playing"
+            ))
+        })?;
+        if !self.connected() {
+            return Err(anyhow::anyhow!(Error::invalid_operation_for_state(
+                "create_station_from_playing",
+                "Disconnected"
+            )));
+        }
+        self.tune_created_station = true;
+        self.send_command(PandoraCommand::CreateStationFromTrack {
+            track_token: track.track_token,
+            as_artist,
+        })
+        .await;
+        Ok(())
+    }
+
+    async fn tune_pending_station(&mut self) -> Result<()> {
+        if let Some(station_id) = self.pending_tune.take() {
+            if self.pandora_stations.contains_key(&station_id) {
+                self.tune(&station_id).await?;
+            } else {
+                // Not in the list yet, keep waiting for it
+                self.pending_tune = Some(station_id);
+            }
+        }
+        Ok(())
+    }
+
+    async fn search(&mut self, text: &str) -> Result<()> {
+        if !self.connected() {
+            return Err(anyhow::anyhow!(Error::invalid_operation_for_state(
+                "search",
+                "Disconnected"
+            )));
+        }
+        self.send_command(PandoraCommand::Search(text.to_string()))
+            .await;
+        Ok(())
+    }
+
+    async fn list_rated_tracks(&mut self, station_id: &str) -> Result<()> {
+        if !self.connected() {
+            return Err(anyhow::anyhow!(Error::invalid_operation_for_state(
+                "list_rated_tracks",
+                "Disconnected"
+            )));
+        }
+        if !self.pandora_stations.contains_key(station_id) {
+            return Err(Error::InvalidStation(station_id.to_string()).into());
+        }
+        self.send_command(PandoraCommand::ListRatedTracks(station_id.to_string()))
             .await;
         Ok(())
     }
 
-    async fn add_station(&mut self, station_id: String, station_name: String) -> Result<()> {
-        if !self.pandora_stations.contains_key(&station_id) {
+    async fn get_station_details(&mut self, station_id: &str) -> Result<()> {
+        if !self.connected() {
+            return Err(anyhow::anyhow!(Error::invalid_operation_for_state(
+                "get_station_details",
+                "Disconnected"
+            )));
+        }
+        // The station may have been deleted since the panel was opened
+        if !self.pandora_stations.contains_key(station_id) {
+            let message = String::from("Not in the station list");
+            warn!("No details for unknown station {station_id}");
+            self.publish_state(State::StationDetailsFailed(station_id.to_string(), message))
+                .await?;
+            return Ok(());
+        }
+        self.pending_station_details = Some(
+            self.send_command(PandoraCommand::GetStationDetails(station_id.to_string()))
+                .await,
+        );
+        Ok(())
+    }
+
+    async fn delete_feedback(
+        &mut self,
+        station_id: &str,
+        feedback_id: &str,
+        music_token: &str,
+    ) -> Result<()> {
+        if !self.connected() {
+            return Err(anyhow::anyhow!(Error::invalid_operation_for_state(
+                "delete_feedback",
+                "Disconnected"
+            )));
+        }
+        self.send_command(PandoraCommand::DeleteFeedback {
+            station_id: station_id.to_string(),
+            feedback_id: feedback_id.to_string(),
+            music_token: music_token.to_string(),
+        })
+        .await;
+        Ok(())
+    }
+
+    async fn set_quickmix(&mut self, station_ids: &[String]) -> Result<()> {
+        if !self.connected() {
+            return Err(anyhow::anyhow!(Error::invalid_operation_for_state(
+                "set_quickmix",
+                "Disconnected"
+            )));
+        }
+        if let Some(station_id) = station_ids
+            .iter()
+            .find(|id| !self.pandora_stations.contains_key(id.as_str()))
+        {
+            return Err(Error::InvalidStation(station_id.to_string()).into());
+        }
+        self.send_command(PandoraCommand::SetQuickMix(station_ids.to_vec()))
+            .await;
+        Ok(())
+    }
+
+    async fn sleep_track(&mut self) -> Result<()> {
+        let track = self.get_playing().cloned().ok_or_else(|| {
+            anyhow::anyhow!(Error::invalid_operation_for_state(
+                "sleep_track",
+                "No track playing"
+            ))
+        })?;
+        if !self.connected() {
+            return Err(anyhow::anyhow!(Error::invalid_operation_for_state(
+                "sleep_track",
+                "Disconnected"
+            )));
+        }
+        self.send_command(PandoraCommand::SleepSong(track.track_token))
+            .await;
+        // No point listening to the rest of a song we're tired of
+        self.stop(StopReason::UserRequest).await
+    }
+
+    async fn bookmark_playing(&mut self, as_artist: bool) -> Result<()> {
+        let track = self.get_playing().cloned().ok_or_else(|| {
+            anyhow::anyhow!(Error::invalid_operation_for_state(
+                "bookmark_playing",
+                "No track playing"
+            ))
+        })?;
+        if !self.connected() {
+            return Err(anyhow::anyhow!(Error::invalid_operation_for_state(
+                "bookmark_playing",
+                "Disconnected"
+            )));
+        }
+        self.send_command(PandoraCommand::AddBookmark {
+            track_token: track.track_token,
+            as_artist,
+        })
+        .await;
+        Ok(())
+    }
+
+    async fn get_bookmarks(&mut self) -> Result<()> {
+        if !self.connected() {
+            return Err(anyhow::anyhow!(Error::invalid_operation_for_state(
+                "get_bookmarks",
+                "Disconnected"
+            )));
+        }
+        self.send_command(PandoraCommand::GetBookmarks).await;
+        Ok(())
+    }
+
+    async fn get_usage_info(&mut self) -> Result<()> {
+        if !self.connected() {
+            return Err(anyhow::anyhow!(Error::invalid_operation_for_state(
+                "get_usage_info",
+                "Disconnected"
+            )));
+        }
+        self.send_command(PandoraCommand::GetUsageInfo).await;
+        Ok(())
+    }
+
+    async fn share_station(&mut self, emails: &[String]) -> Result<()> {
+        let station_id = self.tuned().ok_or_else(|| {
+            anyhow::anyhow!(Error::invalid_operation_for_state(
+                "share_station",
+                "Not tuned to a station"
+            ))
+        })?;
+        if !self.connected() {
+            return Err(anyhow::anyhow!(Error::invalid_operation_for_state(
+                "share_station",
+                "Disconnected"
+            )));
+        }
+        // Typed in by the user, so a mistake is reported rather than treated as an error
+        if emails.is_empty() {
+            let message = String::from("No email addresses to share the station with");
+            warn!("{message}");
+            self.publish_state(State::PandoraError(message)).await?;
+            return Ok(());
+        }
+        self.send_command(PandoraCommand::ShareStation {
+            station_id,
+            emails: emails.to_vec(),
+        })
+        .await;
+        Ok(())
+    }
+
+    async fn accept_shared_station(&mut self, shared: &str, tune: bool) -> Result<()> {
+        if !self.connected() {
+            return Err(anyhow::anyhow!(Error::invalid_operation_for_state(
+                "accept_shared_station",
+                "Disconnected"
+            )));
+        }
+        // Pasted in by the user, so a mistake is reported rather than treated as an error
+        let Some(music_token) = shared_station_token(shared) else {
+            let message = format!("Not a shared station link or token: {shared}");
+            warn!("{message}");
+            self.publish_state(State::PandoraError(message)).await?;
+            return Ok(());
+        };
+        self.tune_created_station = tune;
+        self.send_command(PandoraCommand::AcceptSharedStation(music_token))
+            .await;
+        Ok(())
+    }
+
+    async fn explain_track(&mut self) -> Result<()> {
+        let track = self.get_playing().cloned().ok_or_else(|| {
+            anyhow::anyhow!(Error::invalid_operation_for_state(
+                "explain_track",
+                "No track playing"
+            ))
+        })?;
+        if !self.connected() {
+            return Err(anyhow::anyhow!(Error::invalid_operation_for_state(
+                "explain_track",
+                "Disconnected"
+            )));
+        }
+        self.send_command(PandoraCommand::ExplainTrack(track.track_token))
+            .await;
+        Ok(())
+    }
+
+    async fn get_genre_stations(&mut self) -> Result<()> {
+        if !self.connected() {
+            return Err(anyhow::anyhow!(Error::invalid_operation_for_state(
+                "get_genre_stations",
+                "Disconnected"
+            )));
+        }
+        self.send_command(PandoraCommand::GetGenreStations).await;
+        Ok(())
+    }
+
+    async fn add_station(&mut self, station: Station) -> Result<()> {
+        if !self.pandora_stations.contains_key(&station.station_id) {
             self.pandora_stations
-                .insert(station_id.clone(), station_name.clone());
+                .insert(station.station_id.clone(), station.clone());
             self.dirty |= true;
-            trace!("send notification 'add station {station_name}[{station_id}]'");
-            self.publish_state(State::AddStation(station_name, station_id))
-                .await?;
+            trace!(
+                "send notification 'add station {}[{}]'",
+                station.station_name,
+                station.station_id
+            );
+            self.publish_state(State::AddStation(station)).await?;
         } else {
             trace!("not adding station: already exists");
         }
         Ok(())
     }
 
+    async fn remove_station(&mut self, station_id: &str) -> Result<()> {
+        if self.pandora_stations.remove(station_id).is_none() {
+            trace!("not removing station: not in station list");
+            return Ok(());
+        }
+        self.dirty |= true;
+        trace!("send notification 'remove station {station_id}'");
+        self.publish_state(State::RemoveStation(station_id.to_string()))
+            .await?;
+        if self.tuned().as_deref() == Some(station_id) {
+            info!("Tuned station {station_id} was removed");
+            self.untune().await?;
+        } else {
+            let mut config = self
+                .config
+                .write()
+                .expect("config write for removed station");
+            if config.station_id().as_deref() == Some(station_id) {
+                config.update_from(&PartialConfig::default().station(None));
+            }
+        }
+        Ok(())
+    }
+
+    async fn rename_station_entry(
+        &mut self,
+        station_id: String,
+        station_name: String,
+    ) -> Result<()> {
+        match self.pandora_stations.get_mut(&station_id) {
+            Some(station) if station.station_name != station_name => {
+                station.station_name = station_name.clone()
+            }
+            Some(_) => return Ok(()),
+            None => {
+                trace!("not renaming station: not in station list");
+                return Ok(());
+            }
+        }
+        if let Some((id, name)) = self.pandora_station.as_mut() {
+            if *id == station_id {
+                *name = station_name.clone();
+            }
+        }
+        self.dirty |= true;
+        trace!("send notification 'rename station {station_name}[{station_id}]'");
+        self.publish_state(State::RenameStation(station_name, station_id))
+            .await?;
+        Ok(())
+    }
+
+    async fn rename_station(&mut self, station_id: &str, station_name: &str) -> Result<()> {
+        if !self.connected() {
+            return Err(anyhow::anyhow!(Error::invalid_operation_for_state(
+                "rename_station",
+                "Disconnected"
+            )));
+        }
+        if !self.pandora_stations.contains_key(station_id) {
+            return Err(Error::InvalidStation(station_id.to_string()).into());
+        }
+        self.send_command(PandoraCommand::RenameStation {
+            station_id: station_id.to_string(),
+            station_name: station_name.to_string(),
+        })
+        .await;
+        Ok(())
+    }
+
+    async fn delete_station(&mut self, station_id: &str) -> Result<()> {
+        if !self.connected() {
+            return Err(anyhow::anyhow!(Error::invalid_operation_for_state(
+                "delete_station",
+                "Disconnected"
+            )));
+        }
+        if !self.pandora_stations.contains_key(station_id) {
+            return Err(Error::InvalidStation(station_id.to_string()).into());
+        }
+        self.send_command(PandoraCommand::DeleteStation(station_id.to_string()))
+            .await;
+        Ok(())
+    }
+
     pub(crate) async fn fill_station_list(&mut self) -> Result<()> {
         if !self.connected() {
             return Err(anyhow::anyhow!(Error::invalid_operation_for_state(
@@ -401,14 +831,10 @@ impl Model {
                 "Disconnected"
             )));
         }
-        if self.pending_station_list {
+        if self.pending_station_list.is_some() {
             return Ok(());
         }
-        self.pending_station_list = true;
-        let _ = self
-            .pandora_cmd_tx
-            .send(PandoraCommand::GetStationList)
-            .await;
+        self.pending_station_list = Some(self.send_command(PandoraCommand::GetStationList).await);
         Ok(())
     }
 
@@ -433,15 +859,14 @@ impl Model {
                 "Disconnected"
             )));
         }
-        if self.pending_playlist {
+        if self.pending_playlist.is_some() {
             return Ok(());
         }
-        self.pending_playlist = true;
         debug!("getting new tracks to refill playlist");
-        let _ = self
-            .pandora_cmd_tx
-            .send(PandoraCommand::GetPlaylist(station_id))
-            .await;
+        self.pending_playlist = Some(
+            self.send_command(PandoraCommand::GetPlaylist(station_id))
+                .await,
+        );
         Ok(())
     }
 
@@ -490,6 +915,40 @@ impl Model {
             Request::AddTrackSeed => self.add_track_seed().await?,
             Request::AddArtistSeed => self.add_artist_seed().await?,
             Request::RemoveSeed(seed_id) => self.remove_seed(seed_id).await?,
+            Request::AddSeed(music_token) => self.add_seed(music_token).await?,
+            Request::Search(text) => self.search(text).await?,
+            Request::CreateTrackStation => self.create_station_from_playing(false).await?,
+            Request::CreateArtistStation => self.create_station_from_playing(true).await?,
+            Request::RenameStation {
+                station_id,
+                station_name,
+            } => self.rename_station(station_id, station_name).await?,
+            Request::DeleteStation(station_id) => self.delete_station(station_id).await?,
+            Request::CreateStation { music_token, tune } => {
+                self.create_station(music_token, *tune).await?
+            }
+            Request::GetGenreStations => self.get_genre_stations().await?,
+            Request::ExplainTrack => self.explain_track().await?,
+            Request::SetQuickMix(station_ids) => self.set_quickmix(station_ids).await?,
+            Request::SleepTrack => self.sleep_track().await?,
+            Request::BookmarkSong => self.bookmark_playing(false).await?,
+            Request::BookmarkArtist => self.bookmark_playing(true).await?,
+            Request::GetBookmarks => self.get_bookmarks().await?,
+            Request::GetUsageInfo => self.get_usage_info().await?,
+            Request::ShareStation(emails) => self.share_station(emails).await?,
+            Request::AcceptSharedStation { shared, tune } => {
+                self.accept_shared_station(shared, *tune).await?
+            }
+            Request::ListRatedTracks(station_id) => self.list_rated_tracks(station_id).await?,
+            Request::GetStationDetails(station_id) => self.get_station_details(station_id).await?,
+            Request::DeleteFeedback {
+                station_id,
+                feedback_id,
+                music_token,
+            } => {
+                self.delete_feedback(station_id, feedback_id, music_token)
+                    .await?
+            }
             Request::Quit => self.quit().await?,
         }
         self.dirty |= true;
@@ -581,11 +1040,16 @@ impl Model {
         Ok(old_dirty)
     }
 
-    async fn handle_pandora_result(&mut self, result: PandoraResult) -> Result<()> {
+    async fn handle_pandora_result(&mut self, tagged: TaggedResult) -> Result<()> {
+        let TaggedResult {
+            id,
+            station_id: command_station_id,
+            result,
+        } = tagged;
         match result {
             PandoraResult::Connected => {
                 self.session_connected = true;
-                self.pending_connect = false;
+                self.pending_connect = None;
                 trace!("send notification 'connected'");
                 self.publish_state(State::Connected).await?;
                 if let Some(station_id) = self.tuned() {
@@ -595,20 +1059,88 @@ impl Model {
                 self.publish_state(State::Volume(self.volume())).await?;
             }
             PandoraResult::AuthFailed(message) => {
+                // Also sent when the task fails to re-establish an expired session, in which
+                // case any in-flight requests were abandoned along with the session.
                 self.session_connected = false;
-                self.pending_connect = false;
+                self.pending_connect = None;
+                self.pending_station_list = None;
+                self.pending_playlist = None;
                 error!("{message}");
                 self.publish_state(State::AuthFailed(message)).await?;
                 self.clear_stations().await?;
             }
+            PandoraResult::InvalidConfig(message) => {
+                self.session_connected = false;
+                self.pending_connect = None;
+                // Remember what was wrong, rather than reading the bad settings
+                // again every time drive_state comes around
+                self.invalid_network = Some(
+                    self.config
+                        .read()
+                        .expect("config read for network settings")
+                        .network()
+                        .clone(),
+                );
+                error!("{message}");
+                self.publish_state(State::ConfigError(message)).await?;
+            }
             PandoraResult::Disconnected => {
                 self.session_connected = false;
-                self.pending_connect = false;
+                self.pending_connect = None;
+            }
+            PandoraResult::Cancelled => {
+                trace!("Pandora command {id:?} cancelled");
+                if id.is_none() {
+                    return Ok(());
+                }
+                if id == self.pending_station_list {
+                    self.pending_station_list = None;
+                }
+                if id == self.pending_playlist {
+                    self.pending_playlist = None;
+                }
+                if id == self.pending_station_details {
+                    self.pending_station_details = None;
+                    if let Some(station_id) = command_station_id {
+                        self.publish_state(State::StationDetailsFailed(
+                            station_id,
+                            String::from("Cancelled"),
+                        ))
+                        .await?;
+                    }
+                }
             }
-            PandoraResult::StationList(list) => {
-                self.pending_station_list = false;
-                for (station_id, station_name) in list {
-                    self.add_station(station_id, station_name).await?;
+            PandoraResult::StationList(list, quickmix) => {
+                // Refreshes the task runs on its own don't answer a pending request
+                if id == self.pending_station_list {
+                    self.pending_station_list = None;
+                }
+                // The list may have been changed elsewhere since we last got
+                // it, so bring ours in sync a station at a time
+                let removed: Vec<String> = self
+                    .pandora_stations
+                    .keys()
+                    .filter(|id| !list.contains_key(*id))
+                    .cloned()
+                    .collect();
+                for station_id in removed {
+                    self.remove_station(&station_id).await?;
+                }
+                for (station_id, station) in list {
+                    if self.pandora_stations.contains_key(&station_id) {
+                        self.rename_station_entry(station_id.clone(), station.station_name.clone())
+                            .await?;
+                        // Pick up any other changes, like new station art
+                        self.pandora_stations.insert(station_id, station);
+                    } else {
+                        self.add_station(station).await?;
+                    }
+                }
+                if self.pandora_quickmix != quickmix {
+                    self.pandora_quickmix = quickmix;
+                    trace!("send notification 'quickmix'");
+                    self.publish_state(State::QuickMix(self.pandora_quickmix.clone()))
+                        .await?;
                 }
                 if let Some(station_id) = self.tuned() {
                     if !self.pandora_stations.contains_key(&station_id) {
@@ -616,9 +1148,20 @@ impl Model {
                         self.untune().await?;
                     }
                 }
+                self.tune_pending_station().await?;
+            }
+            PandoraResult::StationListCurrent => {
+                trace!("Station list is unchanged");
+            }
+            PandoraResult::Playlist(_) if id != self.pending_playlist => {
+                // Requested for a station we've since tuned away from
+                debug!(
+                    "Discarding stale playlist for station {}",
+                    command_station_id.unwrap_or_default()
+                );
             }
             PandoraResult::Playlist(tracks) => {
-                self.pending_playlist = false;
+                self.pending_playlist = None;
                 debug!("refilling playlist with new tracks");
                 self.extend_playlist(tracks).await?;
                 self.dirty |= true;
@@ -655,59 +1198,192 @@ impl Model {
                         .iter()
                         .map(|a| (a.artist_name.clone(), a.seed_id.clone()))
                         .collect(),
+                    seed_list: data
+                        .artist_seeds
+                        .iter()
+                        .map(|a| (format!("Artist: {}", a.artist_name), a.seed_id.clone()))
+                        .chain(data.song_seeds.iter().map(|s| {
+                            (
+                                format!("Song: {} by {}", s.song_name, s.artist_name),
+                                s.seed_id.clone(),
+                            )
+                        }))
+                        .chain(
+                            data.genre_seeds
+                                .iter()
+                                .map(|g| (format!("Genre: {}", g.genre_name), g.seed_id.clone())),
+                        )
+                        .collect(),
                 };
                 trace!("received station seeds for {station_id}");
                 self.publish_state(State::StationSeeds(for_ui)).await?;
             }
-            PandoraResult::RatedTracks(_) => {
-                // Rated tracks (loved/banned) for a station; consumed by UI when listing feedback.
-                trace!("received station rated tracks");
+            PandoraResult::RatedTracks(station_id, data) => {
+                trace!("received station rated tracks for {station_id}");
+                self.publish_state(State::RatedTracks(station_id, data))
+                    .await?;
+            }
+            PandoraResult::StationDetails(details) => {
+                if id == self.pending_station_details {
+                    self.pending_station_details = None;
+                }
+                trace!(
+                    "received station details for {}",
+                    details.station.station_id
+                );
+                self.publish_state(State::StationDetails(details)).await?;
+            }
+            PandoraResult::FeedbackDeleted {
+                station_id,
+                feedback_id,
+                music_token,
+            } => {
+                trace!("feedback deleted: {feedback_id}");
+                // The rating may have been for the track that's playing right now
+                if let Some(track) = self
+                    .get_playing_mut()
+                    .filter(|t| t.station_id == station_id && t.music_id == music_token)
+                {
+                    track.song_rating = 0;
+                    self.dirty |= true;
+                    self.notify_playing().await?;
+                }
+                self.publish_state(State::FeedbackDeleted(feedback_id))
+                    .await?;
             }
             PandoraResult::StationCreated {
                 station_token,
-                station_name: _,
+                station_name,
             } => {
-                // New station created; refresh station list so UI sees it.
-                trace!("station created: {station_token}");
-                self.pending_station_list = false;
-                let _ = self
-                    .pandora_cmd_tx
-                    .send(PandoraCommand::GetStationList)
-                    .await;
-                self.pending_station_list = true;
-            }
-            PandoraResult::StationDeleted => {
-                trace!("station deleted");
-                self.pending_station_list = false;
-                let _ = self
-                    .pandora_cmd_tx
-                    .send(PandoraCommand::GetStationList)
-                    .await;
-                self.pending_station_list = true;
+                trace!("station created: {station_name}[{station_token}]");
+                if std::mem::take(&mut self.tune_created_station) {
+                    self.pending_tune = Some(station_token.clone());
+                }
+                if station_name.is_empty() {
+                    // Name unknown; refresh station list so UI sees it.
+                    self.pending_station_list =
+                        Some(self.send_command(PandoraCommand::GetStationList).await);
+                } else {
+                    self.add_station(Station {
+                        station_id: station_token,
+                        station_name,
+                        ..Station::default()
+                    })
+                    .await?;
+                    self.tune_pending_station().await?;
+                }
+            }
+            PandoraResult::StationDeleted(station_id) => {
+                trace!("station deleted: {station_id}");
+                self.remove_station(&station_id).await?;
+            }
+            PandoraResult::StationRenamed {
+                station_id,
+                station_name,
+            } => {
+                trace!("station renamed: {station_name}[{station_id}]");
+                self.rename_station_entry(station_id, station_name).await?;
             }
             PandoraResult::SeedAdded { seed_id, .. } => {
                 trace!("seed added: {seed_id}");
                 if let Some(station_id) = self.tuned() {
-                    self.pending_station_list = false;
-                    let _ = self
-                        .pandora_cmd_tx
-                        .send(PandoraCommand::ListSeeds(station_id))
+                    self.send_command(PandoraCommand::ListSeeds(station_id))
                         .await;
                 }
             }
             PandoraResult::SeedRemoved => {
                 trace!("seed removed");
                 if let Some(station_id) = self.tuned() {
-                    let _ = self
-                        .pandora_cmd_tx
-                        .send(PandoraCommand::ListSeeds(station_id))
+                    self.send_command(PandoraCommand::ListSeeds(station_id))
                         .await;
                 }
             }
-            PandoraResult::Error(msg) => {
-                error!("Pandora task error: {msg}");
-                self.pending_station_list = false;
-                self.pending_playlist = false;
+            PandoraResult::SearchResults(results) => {
+                trace!("received search results for '{}'", results.query);
+                self.publish_state(State::SearchResults(results)).await?;
+            }
+            PandoraResult::QuickMixSet(station_ids) => {
+                trace!("quickmix set to {} stations", station_ids.len());
+                self.pandora_quickmix.station_ids = station_ids;
+                self.publish_state(State::QuickMix(self.pandora_quickmix.clone()))
+                    .await?;
+            }
+            PandoraResult::BookmarkAdded => {
+                info!("Bookmark added");
+            }
+            PandoraResult::SongSlept(track_token) => {
+                info!("Track {track_token} won't be played for a month");
+            }
+            PandoraResult::StationShared(station_id) => {
+                info!("Station {station_id} shared");
+            }
+            PandoraResult::UsageInfo(usage) => {
+                trace!("received usage info: {usage}");
+                self.publish_state(State::UsageInfo(usage)).await?;
+            }
+            PandoraResult::Bookmarks(bookmarks) => {
+                trace!("received {} bookmarks", bookmarks.len());
+                self.publish_state(State::Bookmarks(bookmarks)).await?;
+            }
+            PandoraResult::TrackExplanation {
+                track_token,
+                traits,
+            } => {
+                trace!("received {} traits explaining {track_token}", traits.len());
+                self.publish_state(State::TrackExplanation(track_token, traits))
+                    .await?;
+            }
+            PandoraResult::GenreStations(categories) => {
+                trace!("received {} genre categories", categories.len());
+                self.publish_state(State::GenreStations(categories)).await?;
+            }
+            PandoraResult::Error(err) => {
+                error!("Pandora task error: {err}");
+                // Only the request that failed is no longer in flight
+                if id == self.pending_connect {
+                    self.pending_connect = None;
+                }
+                if id == self.pending_station_list {
+                    self.pending_station_list = None;
+                }
+                if id == self.pending_playlist {
+                    self.pending_playlist = None;
+                }
+                if id.is_some() && id == self.pending_station_details {
+                    self.pending_station_details = None;
+                    if let Some(station_id) = command_station_id {
+                        self.publish_state(State::StationDetailsFailed(
+                            station_id,
+                            err.to_string(),
+                        ))
+                        .await?;
+                    }
+                }
+                match &err {
+                    Error::PandoraAuthFailure(_) => {
+                        // Drop the session, and let drive_state log in again
+                        self.disconnect().await?;
+                    }
+                    Error::PandoraStationUnavailable(station_id) => {
+                        self.remove_station(station_id).await?;
+                    }
+                    Error::PandoraSharedStationNotTransformed(station_id, _) => {
+                        // The station was still added, just not as one of our own
+                        if std::mem::take(&mut self.tune_created_station) {
+                            self.pending_tune = Some(station_id.clone());
+                        }
+                        self.pending_station_list =
+                            Some(self.send_command(PandoraCommand::GetStationList).await);
+                    }
+                    _ => (),
+                }
+                self.publish_state(State::PandoraError(err.to_string()))
+                    .await?;
+            }
+            PandoraResult::Retrying { what, delay } => {
+                // Whatever was waiting on the request stays pending until it's done
+                info!("Retrying Pandora {what} in {}s", delay.as_secs());
+                self.publish_state(State::Retrying(what, delay)).await?;
             }
             PandoraResult::QuitAck => {}
         }
@@ -942,3 +1618,227 @@ impl Drop for Model {
         trace!("Application data model has been dropped");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, RwLock};
+
+    use super::*;
+    use crate::config::Config;
+    use crate::pandora::mock::MockPandora;
+
+    const STATE_TIMEOUT: Duration = Duration::from_secs(10);
+
+    async fn wait_for_state(states: &mut StateReceiver, pred: impl Fn(&State) -> bool) {
+        tokio::time::timeout(STATE_TIMEOUT, async {
+            loop {
+                let state = states.recv().await.expect("model state channel open");
+                if pred(&state) {
+                    return;
+                }
+            }
+        })
+        .await
+        .expect("timed out waiting for model state");
+    }
+
+    #[tokio::test]
+    async fn connect_tune_and_queue_tracks() {
+        let mock = MockPandora::start().await;
+        let config = mock.config();
+        config
+            .write()
+            .expect("config write for test station")
+            .update_from(&PartialConfig::default().station(Some(String::from("101"))));
+
+        let (pandora_cmd_tx, pandora_cmd_rx) = mpsc::channel(32);
+        let (pandora_result_tx, pandora_result_rx) = mpsc::channel(32);
+        tokio::spawn(crate::pandora::run_pandora_task(
+            config.clone(),
+            pandora_cmd_rx,
+            pandora_result_tx,
+        ));
+        let mut model = Model::new(config, pandora_cmd_tx, pandora_result_rx);
+        let mut states = model.updates_channel();
+        let requests = model.request_channel();
+        let model_handle =
+            tokio::spawn(async move { model.run_until_quit(Duration::from_millis(10)).await });
+
+        wait_for_state(&mut states, |s| matches!(s, State::Connected)).await;
+        wait_for_state(
+            &mut states,
+            |s| matches!(s, State::Tuned(id) if id == "101"),
+        )
+        .await;
+        wait_for_state(
+            &mut states,
+            |s| matches!(s, State::TrackCaching(t) if t.station_id == "101"),
+        )
+        .await;
+
+        requests.send(Request::Quit).await.expect("model running");
+        wait_for_state(&mut states, |s| matches!(s, State::Quit)).await;
+        tokio::time::timeout(STATE_TIMEOUT, model_handle)
+            .await
+            .expect("timed out waiting for model to quit")
+            .expect("model task")
+            .expect("model ran without errors");
+    }
+
+    #[tokio::test]
+    async fn playlist_for_previous_station_is_discarded() {
+        let config = Arc::new(RwLock::new(Config::default()));
+        let (pandora_cmd_tx, mut pandora_cmd_rx) = mpsc::channel(32);
+        let (_pandora_result_tx, pandora_result_rx) = mpsc::channel(32);
+        let mut model = Model::new(config, pandora_cmd_tx, pandora_result_rx);
+        model.session_connected = true;
+        for (id, name) in [("101", "Rock"), ("102", "Jazz")] {
+            model.pandora_stations.insert(
+                id.to_string(),
+                Station {
+                    station_id: id.to_string(),
+                    station_name: name.to_string(),
+                    ..Station::default()
+                },
+            );
+        }
+
+        model.tune("101").await.expect("tune first station");
+        model.refill_playlist().await.expect("request playlist");
+        let stale_id = model.pending_playlist.expect("playlist requested");
+        model.tune("102").await.expect("tune second station");
+        model.refill_playlist().await.expect("request playlist");
+        let current_id = model.pending_playlist.expect("playlist requested");
+        assert_ne!(stale_id, current_id);
+
+        model
+            .handle_pandora_result(TaggedResult {
+                id: Some(stale_id),
+                station_id: Some(String::from("101")),
+                result: PandoraResult::Playlist(Vec::new()),
+            })
+            .await
+            .expect("handle stale playlist");
+        assert_eq!(model.pending_playlist, Some(current_id));
+
+        model
+            .handle_pandora_result(TaggedResult {
+                id: Some(current_id),
+                station_id: Some(String::from("102")),
+                result: PandoraResult::Playlist(Vec::new()),
+            })
+            .await
+            .expect("handle current playlist");
+        assert_eq!(model.pending_playlist, None);
+
+        let mut playlist_requests = Vec::new();
+        while let Ok(TaggedCommand { id, command }) = pandora_cmd_rx.try_recv() {
+            if let PandoraCommand::GetPlaylist(station_id) = command {
+                playlist_requests.push((id, station_id));
+            }
+        }
+        assert_eq!(
+            playlist_requests,
+            vec![
+                (stale_id, String::from("101")),
+                (current_id, String::from("102"))
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn station_list_is_requested_again_after_disconnect() {
+        let config = Arc::new(RwLock::new(Config::default()));
+        let (pandora_cmd_tx, mut pandora_cmd_rx) = mpsc::channel(32);
+        let (_pandora_result_tx, pandora_result_rx) = mpsc::channel(32);
+        let mut model = Model::new(config, pandora_cmd_tx, pandora_result_rx);
+        model.session_connected = true;
+        model
+            .fill_station_list()
+            .await
+            .expect("request station list");
+        let dropped_id = model.pending_station_list.expect("station list requested");
+
+        model.disconnect().await.expect("disconnect");
+        model.session_connected = true;
+        model
+            .fill_station_list()
+            .await
+            .expect("request station list");
+        let retried_id = model.pending_station_list.expect("station list requested");
+        assert_ne!(dropped_id, retried_id);
+
+        let mut list_requests = Vec::new();
+        while let Ok(TaggedCommand { id, command }) = pandora_cmd_rx.try_recv() {
+            if let PandoraCommand::GetStationList = command {
+                list_requests.push(id);
+            }
+        }
+        assert_eq!(list_requests, vec![dropped_id, retried_id]);
+    }
+
+    #[tokio::test]
+    async fn invalid_network_settings_wait_for_a_change() {
+        let config = Arc::new(RwLock::new(Config::default()));
+        let (pandora_cmd_tx, mut pandora_cmd_rx) = mpsc::channel(32);
+        let (_pandora_result_tx, pandora_result_rx) = mpsc::channel(32);
+        let mut model = Model::new(config.clone(), pandora_cmd_tx, pandora_result_rx);
+        let mut states = model.updates_channel();
+        model.connect().await.expect("connect");
+        let id = model.pending_connect;
+        model
+            .handle_pandora_result(TaggedResult {
+                id,
+                station_id: None,
+                result: PandoraResult::InvalidConfig(String::from("bad proxy")),
+            })
+            .await
+            .expect("handle invalid config");
+        wait_for_state(&mut states, |s| matches!(s, State::ConfigError(_))).await;
+
+        model.connect().await.expect("connect");
+        assert_eq!(model.pending_connect, None);
+
+        let network = NetworkSettings {
+            proxy: Some(String::from("http://proxy:3128")),
+            ..NetworkSettings::default()
+        };
+        config
+            .write()
+            .expect("config write for test network")
+            .update_from(&PartialConfig {
+                network: Some(network),
+                ..PartialConfig::default()
+            });
+        model.connect().await.expect("connect");
+        assert!(model.pending_connect.is_some());
+
+        let mut connects = 0;
+        while let Ok(TaggedCommand { command, .. }) = pandora_cmd_rx.try_recv() {
+            if let PandoraCommand::Connect = command {
+                connects += 1;
+            }
+        }
+        assert_eq!(connects, 2);
+    }
+
+    #[tokio::test]
+    async fn station_details_for_unknown_station_fail() {
+        let config = Arc::new(RwLock::new(Config::default()));
+        let (pandora_cmd_tx, mut pandora_cmd_rx) = mpsc::channel(32);
+        let (_pandora_result_tx, pandora_result_rx) = mpsc::channel(32);
+        let mut model = Model::new(config, pandora_cmd_tx, pandora_result_rx);
+        let mut states = model.updates_channel();
+        model.session_connected = true;
+        model
+            .get_station_details("999")
+            .await
+            .expect("unknown station reported, not an error");
+        wait_for_state(
+            &mut states,
+            |s| matches!(s, State::StationDetailsFailed(id, _) if id == "999"),
+        )
+        .await;
+        assert!(pandora_cmd_rx.try_recv().is_err());
+    }
+}
@@ -13,6 +13,21 @@ use mpris_server::{Metadata, Property, Signal, Time};
 mod mpris_intf;
 use mpris_intf::{MprisInterface, MprisState};
 
+/// Metadata for the playing track, with the traits explaining why it was
+/// chosen (if known) as the comment.
+fn playing_metadata(track: &Track, explanation: &[String]) -> Metadata {
+    let builder = Metadata::builder()
+        .length(Time::from_millis(track.track_length.as_millis() as i64))
+        .album(track.album_name.clone())
+        .artist([track.artist_name.clone()])
+        .title(track.title.clone());
+    if explanation.is_empty() {
+        builder.build()
+    } else {
+        builder.comment(explanation.to_vec()).build()
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct MprisUi {
     server: mpris_server::Server<MprisInterface>,
@@ -50,17 +65,21 @@ impl MprisUi {
     }
 
     async fn playing_track(&mut self, track: Track) -> Result<()> {
-        {
+        // The explanation is only fetched on request (`panharmonicon:explain`),
+        // but TrackStarting is re-sent for the same track when it's rated, so
+        // keep any we already have for it
+        let explanation = {
             let mut state = self.shared_state.write().await;
             state.playing = Some((track.clone(), Duration::ZERO, false));
             state.tracklist = vec![track.clone()];
-        }
-        let metadata = Metadata::builder()
-            .length(Time::from_millis(track.track_length.as_millis() as i64))
-            .album(track.album_name.clone())
-            .artist([track.artist_name.clone()])
-            .title(track.title.clone())
-            .build();
+            state
+                .explanation
+                .as_ref()
+                .filter(|(token, _)| *token == track.track_token)
+                .map(|(_, traits)| traits.clone())
+                .unwrap_or_default()
+        };
+        let metadata = playing_metadata(&track, &explanation);
         self.server
             .properties_changed([
                 Property::Metadata(metadata),
@@ -70,6 +89,26 @@ impl MprisUi {
         Ok(())
     }
 
+    async fn explained_track(&mut self, track_token: String, traits: Vec<String>) -> Result<()> {
+        let track = {
+            let mut state = self.shared_state.write().await;
+            let Some((track, _, _)) = state
+                .playing
+                .as_ref()
+                .filter(|(t, _, _)| t.track_token == track_token)
+            else {
+                return Ok(());
+            };
+            let track = track.clone();
+            state.explanation = Some((track_token, traits.clone()));
+            track
+        };
+        self.server
+            .properties_changed([Property::Metadata(playing_metadata(&track, &traits))])
+            .await?;
+        Ok(())
+    }
+
     async fn update_playing(&mut self, elapsed: Duration, paused: bool) -> Result<()> {
         {
             let mut state = self.shared_state.write().await;
@@ -110,20 +149,50 @@ impl MprisUi {
         while let Ok(message) = self.state_receiver.try_recv() {
             match message {
                 State::AuthFailed(_) => self.update_state_stopped().await?,
+                State::ConfigError(_) => self.update_state_stopped().await?,
+                State::PandoraError(_) => (),
+                State::Retrying(_, _) => (),
                 State::Connected => self.update_state_stopped().await?,
                 State::Disconnected => self.update_state_stopped().await?,
-                State::AddStation(name, id) => {
+                State::AddStation(station) => {
+                    let mut state = self.shared_state.write().await;
+                    state
+                        .playlists
+                        .insert(station.station_id, station.station_name);
+                }
+                State::RemoveStation(id) => {
                     let mut state = self.shared_state.write().await;
+                    state.playlists.remove(&id);
+                    if state
+                        .active_playlist
+                        .as_ref()
+                        .is_some_and(|(active_id, _)| *active_id == id)
+                    {
+                        state.active_playlist = None;
+                    }
+                }
+                State::RenameStation(name, id) => {
+                    let mut state = self.shared_state.write().await;
+                    if let Some((_, active_name)) = state
+                        .active_playlist
+                        .as_mut()
+                        .filter(|(active_id, _)| *active_id == id)
+                    {
+                        *active_name = name.clone();
+                    }
                     state.playlists.insert(id, name);
                 }
-                State::Tuned(name) => {
+                State::Tuned(id) => {
                     let mut state = self.shared_state.write().await;
                     state.active_playlist = state
                         .playlists
-                        .iter()
-                        .find(|(_, v)| *v == &name)
+                        .get_key_value(&id)
                         .map(|(k, v)| (k.clone(), v.clone()));
                 }
+                State::QuickMix(quickmix) => {
+                    let mut state = self.shared_state.write().await;
+                    state.quickmix_id = quickmix.station_id;
+                }
                 State::TrackStarting(track) => self.playing_track(track).await?,
                 State::Next(next_track) => {
                     let mut state = self.shared_state.write().await;
@@ -143,6 +212,17 @@ impl MprisUi {
                 State::Stopped(_) => self.update_state_stopped().await?,
                 State::Buffering => self.update_state_stopped().await?,
                 State::StationSeeds(_) => (),
+                State::SearchResults(_) => (),
+                State::GenreStations(_) => (),
+                State::Bookmarks(_) => (),
+                State::UsageInfo(_) => (),
+                State::TrackExplanation(token, traits) => {
+                    self.explained_track(token, traits).await?
+                }
+                State::RatedTracks(_, _) => (),
+                State::StationDetails(_) => (),
+                State::StationDetailsFailed(_, _) => (),
+                State::FeedbackDeleted(_) => (),
                 State::TrackCaching(_) => (),
                 State::Muted => (),
                 State::Unmuted => (),
@@ -15,17 +15,52 @@ use mpris_server::{LoopStatus, PlaybackRate, PlaylistId, PlaylistOrdering, Track
 use mpris_server::{Metadata, PlaybackStatus, Playlist, Time, Volume};
 use mpris_server::{PlayerInterface, PlaylistsInterface, RootInterface, TrackListInterface};
 
+/// URI scheme accepted by `OpenUri` for panharmonicon actions that have no
+/// MPRIS equivalent, e.g. `panharmonicon:station-from-song`.
+const ACTION_URI_SCHEME: &str = "panharmonicon";
+
+/// Map an action URI onto the request it invokes.
+fn action_request(uri: &str) -> Option<Request> {
+    let action = uri.strip_prefix(ACTION_URI_SCHEME)?.strip_prefix(':')?;
+    match action.trim_start_matches('/') {
+        "station-from-song" => Some(Request::CreateTrackStation),
+        "station-from-artist" => Some(Request::CreateArtistStation),
+        "tired" => Some(Request::SleepTrack),
+        "explain" => Some(Request::ExplainTrack),
+        _ => None,
+    }
+}
+
 /// Shared state between MprisUi (writer) and MprisInterface (reader) so D-Bus
 /// property reads see the same playback state as emitted signals.
 #[derive(Debug, Default)]
 pub(crate) struct MprisState {
     pub(crate) playlists: HashMap<String, String>,
     pub(crate) active_playlist: Option<(String, String)>,
+    /// Id of the QuickMix (Shuffle) station, which is marked in its playlist name.
+    pub(crate) quickmix_id: Option<String>,
     pub(crate) tracklist: Vec<Track>,
     pub(crate) playing: Option<(Track, Duration, bool)>,
+    /// Traits explaining why a track was chosen (track token, traits).
+    pub(crate) explanation: Option<(String, Vec<String>)>,
     pub(crate) volume: f32,
 }
 
+impl MprisState {
+    fn playlist(&self, id: &str, name: &str) -> Option<Playlist> {
+        let name = if self.quickmix_id.as_deref() == Some(id) {
+            format!("[Shuffle] {name}")
+        } else {
+            name.to_string()
+        };
+        Some(Playlist {
+            id: PlaylistId::try_from(id).ok()?,
+            name,
+            icon: Uri::new(),
+        })
+    }
+}
+
 pub(crate) struct MprisInterface {
     state: Arc<RwLock<MprisState>>,
     request_sender: RequestSender,
@@ -91,7 +126,7 @@ impl RootInterface for MprisInterface {
     }
 
     async fn supported_uri_schemes(&self) -> zbus::fdo::Result<Vec<String>> {
-        Ok(vec![])
+        Ok(vec![ACTION_URI_SCHEME.to_string()])
     }
 
     async fn supported_mime_types(&self) -> zbus::fdo::Result<Vec<String>> {
@@ -137,7 +172,10 @@ impl PlayerInterface for MprisInterface {
         Ok(())
     }
 
-    async fn open_uri(&self, _uri: String) -> zbus::fdo::Result<()> {
+    async fn open_uri(&self, uri: String) -> zbus::fdo::Result<()> {
+        let request = action_request(&uri)
+            .ok_or_else(|| zbus::fdo::Error::InvalidArgs(format!("Unsupported URI {uri}")))?;
+        self.publish_zrequest(request)?;
         Ok(())
     }
 
@@ -178,7 +216,15 @@ impl PlayerInterface for MprisInterface {
     async fn metadata(&self) -> zbus::fdo::Result<Metadata> {
         let guard = self.state.read().await;
         if let Some((t, _, _)) = &guard.playing {
-            Ok(Metadata::from(t))
+            let mut metadata = Metadata::from(t);
+            if let Some((_, traits)) = guard
+                .explanation
+                .as_ref()
+                .filter(|(token, traits)| *token == t.track_token && !traits.is_empty())
+            {
+                metadata.set_comment(Some(traits.clone()));
+            }
+            Ok(metadata)
         } else {
             Ok(Metadata::default())
         }
@@ -307,13 +353,7 @@ impl PlaylistsInterface for MprisInterface {
         let playlists: Vec<Playlist> = guard
             .playlists
             .iter()
-            .filter_map(|(id, name)| {
-                Some(Playlist {
-                    id: PlaylistId::try_from(id.as_str()).ok()?,
-                    name: name.clone(),
-                    icon: Uri::new(),
-                })
-            })
+            .filter_map(|(id, name)| guard.playlist(id, name))
             .take(max_count as usize)
             .collect();
         Ok(playlists)
@@ -330,12 +370,9 @@ impl PlaylistsInterface for MprisInterface {
 
     async fn active_playlist(&self) -> zbus::fdo::Result<Option<Playlist>> {
         let guard = self.state.read().await;
-        Ok(guard.active_playlist.as_ref().and_then(|(id, name)| {
-            Some(Playlist {
-                id: PlaylistId::try_from(id.as_str()).ok()?,
-                name: name.clone(),
-                icon: Uri::new(),
-            })
-        }))
+        Ok(guard
+            .active_playlist
+            .as_ref()
+            .and_then(|(id, name)| guard.playlist(id, name)))
     }
 }
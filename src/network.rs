@@ -0,0 +1,131 @@
+//! HTTP clients for Pandora API requests and track downloads, both built from
+//! the network settings in the config so that they reach the network the
+//! same way.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use log::debug;
+
+use crate::config::NetworkSettings;
+
+fn read_ca_certificates(settings: &NetworkSettings) -> Result<Vec<Vec<u8>>> {
+    settings
+        .ca_certificates
+        .iter()
+        .map(|path| {
+            std::fs::read(path)
+                .with_context(|| format!("Failed reading CA certificate file {}", path.display()))
+        })
+        .collect()
+}
+
+/// Client for track downloads.
+pub(crate) fn http_client(settings: &NetworkSettings) -> Result<reqwest::Client> {
+    let mut builder = reqwest::Client::builder();
+    if let Some(url) = &settings.proxy {
+        debug!("Using proxy {url} for track downloads");
+        let proxy = reqwest::Proxy::all(url.as_str())
+            .with_context(|| format!("Invalid proxy URL {url}"))?
+            .no_proxy(
+                settings
+                    .no_proxy
+                    .as_deref()
+                    .and_then(reqwest::NoProxy::from_string),
+            );
+        builder = builder.proxy(proxy);
+    }
+    for pem in read_ca_certificates(settings)? {
+        let certs = reqwest::Certificate::from_pem_bundle(&pem)
+            .context("Failed parsing CA certificate file")?;
+        builder = builder.tls_certs_merge(certs);
+    }
+    if let Some(secs) = settings.connect_timeout_secs {
+        builder = builder.connect_timeout(Duration::from_secs(secs));
+    }
+    if let Some(secs) = settings.read_timeout_secs {
+        builder = builder.read_timeout(Duration::from_secs(secs));
+    }
+    builder
+        .build()
+        .context("Failed building HTTP client for track downloads")
+}
+
+/// Client for the Pandora API session.  `pandora_api` is built against an
+/// older reqwest than we are, so its client has to be too.
+pub(crate) fn pandora_api_client(settings: &NetworkSettings) -> Result<reqwest012::Client> {
+    let mut builder = reqwest012::Client::builder();
+    if let Some(url) = &settings.proxy {
+        debug!("Using proxy {url} for Pandora API requests");
+        let proxy = reqwest012::Proxy::all(url.as_str())
+            .with_context(|| format!("Invalid proxy URL {url}"))?
+            .no_proxy(
+                settings
+                    .no_proxy
+                    .as_deref()
+                    .and_then(reqwest012::NoProxy::from_string),
+            );
+        builder = builder.proxy(proxy);
+    }
+    for pem in read_ca_certificates(settings)? {
+        let certs = reqwest012::Certificate::from_pem_bundle(&pem)
+            .context("Failed parsing CA certificate file")?;
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+    if let Some(secs) = settings.connect_timeout_secs {
+        builder = builder.connect_timeout(Duration::from_secs(secs));
+    }
+    if let Some(secs) = settings.read_timeout_secs {
+        builder = builder.read_timeout(Duration::from_secs(secs));
+    }
+    builder
+        .build()
+        .context("Failed building HTTP client for Pandora API requests")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn with_proxy(proxy: &str) -> NetworkSettings {
+        NetworkSettings {
+            proxy: Some(proxy.to_string()),
+            ..NetworkSettings::default()
+        }
+    }
+
+    fn with_ca_certificate(path: &str) -> NetworkSettings {
+        NetworkSettings {
+            ca_certificates: vec![PathBuf::from(path)],
+            ..NetworkSettings::default()
+        }
+    }
+
+    #[test]
+    fn invalid_proxy_is_rejected() {
+        let settings = with_proxy("http://[::1");
+        let err = http_client(&settings).expect_err("track download client");
+        assert!(format!("{err:#}").contains("Invalid proxy URL"), "{err:#}");
+        let err = pandora_api_client(&settings).expect_err("Pandora API client");
+        assert!(format!("{err:#}").contains("Invalid proxy URL"), "{err:#}");
+    }
+
+    #[test]
+    fn missing_ca_certificate_is_rejected() {
+        let settings = with_ca_certificate("/nonexistent/panharmonicon-ca.pem");
+        let err = http_client(&settings).expect_err("track download client");
+        assert!(
+            format!("{err:#}").contains("Failed reading CA certificate file"),
+            "{err:#}"
+        );
+        let err = pandora_api_client(&settings).expect_err("Pandora API client");
+        assert!(
+            format!("{err:#}").contains("Failed reading CA certificate file"),
+            "{err:#}"
+        );
+    }
+}
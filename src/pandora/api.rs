@@ -1,16 +1,21 @@
 #![allow(dead_code)]
 
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
 use anyhow::{Context, Result};
 use log::trace;
 
 use pandora_api::json::auth::{PartnerLogin, UserLogin};
+use pandora_api::json::bookmark::*;
+use pandora_api::json::errors::JsonErrorKind;
 use pandora_api::json::music::*;
 use pandora_api::json::station::*;
 use pandora_api::json::track::*;
 use pandora_api::json::user::*;
 use pandora_api::json::{PandoraJsonApiRequest, ToEncryptionTokens};
 
-use crate::config::SharedConfig;
+use crate::config::{AudioQuality, PartnerProfile, SharedConfig};
 use crate::errors::Error;
 use crate::track::Track;
 
@@ -20,13 +25,13 @@ struct PartnerKeys {
     decrypt: String,
 }
 
-impl PartnerKeys {
+impl From<&PartnerProfile> for PartnerKeys {
     /// Create a new instance of partner keys with the keys
-    /// for the "android" partner.
-    fn new_android() -> Self {
+    /// for the configured partner.
+    fn from(profile: &PartnerProfile) -> Self {
         Self {
-            encrypt: String::from("6#26FRL$ZWD"),
-            decrypt: String::from("R=U!LH$O2B#"),
+            encrypt: profile.encrypt_key.clone(),
+            decrypt: profile.decrypt_key.clone(),
         }
     }
 }
@@ -41,7 +46,173 @@ impl ToEncryptionTokens for PartnerKeys {
     }
 }
 
-const ANDROID_ENDPOINT: &str = "https://tuner.pandora.com/services/json";
+/// Whether an error returned from a session request indicates that the auth
+/// tokens held by the session have expired or otherwise been invalidated, in
+/// which case the session has to log in again before any request will succeed.
+pub(crate) fn session_expired(err: &anyhow::Error) -> bool {
+    err.chain()
+        .filter_map(|e| e.downcast_ref::<pandora_api::errors::Error>())
+        .any(|e| {
+            matches!(
+                e,
+                pandora_api::errors::Error::PandoraJsonRequestError(je)
+                    if matches!(je.kind(), JsonErrorKind::InvalidAuthToken)
+            )
+        })
+}
+
+/// Sort an error returned from a session request into the class of failure
+/// it represents, from the Pandora API fault code or the transport error
+/// underlying it. `station_id` is the station the request was for, if any.
+pub(crate) fn classify_error(err: &anyhow::Error, station_id: Option<&str>) -> Error {
+    let message = format!("{err:#}");
+    if err
+        .chain()
+        .filter_map(|e| e.downcast_ref::<Error>())
+        .any(|e| e.missing_auth_token())
+    {
+        return Error::PandoraAuthFailure(message);
+    }
+    let fault = err
+        .chain()
+        .filter_map(|e| e.downcast_ref::<pandora_api::errors::Error>())
+        .find_map(|e| match e {
+            pandora_api::errors::Error::PandoraJsonRequestError(je) => Some(je.kind()),
+            _ => None,
+        });
+    match fault {
+        Some(
+            JsonErrorKind::InvalidAuthToken
+            | JsonErrorKind::InvalidPartnerLogin
+            | JsonErrorKind::ListenerNotAuthorized
+            | JsonErrorKind::UserNotAuthorized,
+        ) => Error::PandoraAuthFailure(message),
+        Some(JsonErrorKind::PlaylistExceeded) => Error::PandoraRateLimited(message),
+        Some(JsonErrorKind::StationDoesNotExist) => match station_id {
+            Some(station_id) => Error::PandoraStationUnavailable(station_id.to_string()),
+            None => Error::PandoraRequestFailure(message),
+        },
+        Some(
+            JsonErrorKind::InternalError
+            | JsonErrorKind::MaintenanceMode
+            | JsonErrorKind::ReadOnlyMode
+            | JsonErrorKind::InsufficientConnectivity,
+        ) => Error::PandoraUnavailable(message),
+        Some(_) => Error::PandoraRequestFailure(message),
+        None if err.chain().any(|e| {
+            e.is::<reqwest012::Error>() || e.is::<reqwest::Error>() || e.is::<std::io::Error>()
+        }) =>
+        {
+            Error::PandoraNetworkFailure(message)
+        }
+        None => Error::PandoraRequestFailure(message),
+    }
+}
+
+/// Find the QuickMix (Shuffle) station in a station list, and the stations
+/// it plays from.
+pub(crate) fn quickmix(stations: &[Station]) -> crate::pandora::QuickMix {
+    stations
+        .iter()
+        .find(|s| {
+            s.optional
+                .get("isQuickMix")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false)
+        })
+        .map(|s| crate::pandora::QuickMix {
+            station_id: Some(s.station_id.clone()),
+            station_ids: s
+                .optional
+                .get("quickMixStationIds")
+                .and_then(|v| v.as_array())
+                .map(|ids| {
+                    ids.iter()
+                        .filter_map(|id| id.as_str().map(String::from))
+                        .collect()
+                })
+                .unwrap_or_default(),
+        })
+        .unwrap_or_default()
+}
+
+/// Build a station from its id and name, and the optional attributes that
+/// both station list entries and `getStation` responses carry.
+pub(crate) fn station_info(
+    station_id: String,
+    station_name: String,
+    optional: &HashMap<String, serde_json::Value>,
+) -> crate::pandora::Station {
+    crate::pandora::Station {
+        station_id,
+        station_name,
+        art_url: optional
+            .get("artUrl")
+            .and_then(|v| v.as_str())
+            .filter(|url| !url.is_empty())
+            .map(String::from),
+        genres: optional
+            .get("genre")
+            .and_then(|v| v.as_array())
+            .map(|genres| {
+                genres
+                    .iter()
+                    .filter_map(|g| g.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        // Pandora dates are objects with the milliseconds since the epoch in "time"
+        date_created: optional
+            .get("dateCreated")
+            .and_then(|v| v.get("time"))
+            .and_then(|v| v.as_i64())
+            .and_then(chrono::DateTime::from_timestamp_millis)
+            .map(|d| d.date_naive()),
+    }
+}
+
+/// Convert the seeds from an extended `getStation` response.
+fn station_seeds(music: Option<StationSeeds>) -> crate::pandora::StationSeedsData {
+    use crate::pandora::{ArtistSeedInfo, GenreSeedInfo, SongSeedInfo, StationSeedsData};
+    let music = music.unwrap_or(StationSeeds {
+        songs: vec![],
+        artists: vec![],
+        genres: vec![],
+    });
+    let artist_seeds = music
+        .artists
+        .into_iter()
+        .map(|a| ArtistSeedInfo {
+            seed_id: a.seed_id,
+            music_token: a.music_token,
+            artist_name: a.artist_name,
+        })
+        .collect();
+    let song_seeds = music
+        .songs
+        .into_iter()
+        .map(|s| SongSeedInfo {
+            seed_id: s.seed_id,
+            music_token: s.music_token,
+            song_name: s.song_name,
+            artist_name: s.artist_name,
+        })
+        .collect();
+    let genre_seeds = music
+        .genres
+        .into_iter()
+        .map(|g| GenreSeedInfo {
+            seed_id: g.seed_id,
+            music_token: g.music_token,
+            genre_name: g.genre_name,
+        })
+        .collect();
+    StationSeedsData {
+        artist_seeds,
+        song_seeds,
+        genre_seeds,
+    }
+}
 
 /// Encapsulates all data that needs to be tracked as part of a login session
 /// with Pandora.  The actual reqwest Client is created by and stored on the
@@ -50,19 +221,33 @@ const ANDROID_ENDPOINT: &str = "https://tuner.pandora.com/services/json";
 pub(crate) struct PandoraSession {
     config: SharedConfig,
     inner: pandora_api::json::PandoraSession,
+    /// Checksum of the last station list retrieved, for detecting changes to
+    /// it.  Shared between clones of the session, so that it's kept however
+    /// many commands are running at once.
+    station_list_checksum: Arc<Mutex<Option<String>>>,
 }
 
-// TODO: filter responses for ones that indicate the session is
-// no longer valid, re-create session and retry?
 impl PandoraSession {
-    /// Instantiate a new PandoraSession.
-    pub fn new(config: SharedConfig) -> Self {
+    /// Instantiate a new PandoraSession, with an HTTP client built from the
+    /// network settings in the config.
+    pub fn new(config: SharedConfig) -> Result<Self> {
+        let (partner, client) = {
+            let config = config.read().expect("config read for new session");
+            (
+                config.partner_profile().clone(),
+                crate::network::pandora_api_client(config.network())?,
+            )
+        };
         let inner: pandora_api::json::PandoraSession = pandora_api::json::PandoraSession::new(
-            None,
-            &PartnerKeys::new_android(),
-            &String::from(ANDROID_ENDPOINT),
+            Some(client),
+            &PartnerKeys::from(&partner),
+            &partner.endpoint,
         );
-        Self { config, inner }
+        Ok(Self {
+            config,
+            inner,
+            station_list_checksum: Arc::new(Mutex::new(None)),
+        })
     }
 
     pub fn connected(&self) -> bool {
@@ -105,11 +290,17 @@ impl PandoraSession {
         }
 
         trace!("Partner login");
+        let partner = self
+            .config
+            .read()
+            .expect("config read for partner_login partner profile")
+            .partner_profile()
+            .clone();
         PartnerLogin::new(
-            "android",
-            "AC7IBG09A3DTSYM4R41UJWL07VLN8JI7",
-            "android-generic",
-            Some("5".to_string()),
+            &partner.username,
+            &partner.password,
+            &partner.device_model,
+            partner.version,
         )
         .merge_response(&mut self.inner)
         .await?;
@@ -187,6 +378,30 @@ impl PandoraSession {
         .map_err(anyhow::Error::from)
     }
 
+    /// Search for music, converting the matches into a single list ordered
+    /// artists first, then songs, then genre stations.
+    pub async fn search_music(&mut self, text: &str) -> Result<crate::pandora::SearchResults> {
+        use crate::pandora::{MusicMatch, SearchResults};
+        let resp = self.search(text).await?;
+        let artists = resp.artists.into_iter().map(|a| MusicMatch::Artist {
+            artist_name: a.artist_name,
+            music_token: a.music_token,
+        });
+        let songs = resp.songs.into_iter().map(|s| MusicMatch::Song {
+            song_name: s.song_name,
+            artist_name: s.artist_name,
+            music_token: s.music_token,
+        });
+        let genres = resp.genre_stations.into_iter().map(|g| MusicMatch::Genre {
+            station_name: g.station_name,
+            music_token: g.music_token,
+        });
+        Ok(SearchResults {
+            query: text.to_string(),
+            matches: artists.chain(songs).chain(genres).collect(),
+        })
+    }
+
     pub async fn get_track(&mut self, music_id: &str) -> Result<GetTrackResponse> {
         trace!("getTrack()");
         let request = GetTrack::from(&music_id);
@@ -272,17 +487,11 @@ impl PandoraSession {
     ) -> Result<AddMusicResponse> {
         trace!("addMusic()");
         let request = AddMusic::new(station_token, music_token);
-        // Catch request errors, reconnect, and retry
-        match request.response(&mut self.inner).await {
-            Err(_) => {
-                self.user_login().await.context(
-                    "Failed to ensure valid user login before retrying add feedback request",
-                )?;
-                request.response(&mut self.inner).await
-            }
-            res => res,
-        }
-        .map_err(anyhow::Error::from)
+        // Not re-sent on failure, since the seed may already have been added
+        request
+            .response(&mut self.inner)
+            .await
+            .map_err(anyhow::Error::from)
     }
 
     pub async fn delete_music(&mut self, seed_id: &str) -> Result<()> {
@@ -308,17 +517,12 @@ impl PandoraSession {
     ) -> Result<CreateStationResponse> {
         trace!("createStation()");
         let request = CreateStation::new_from_track_song(track_token);
-        // Catch request errors, reconnect, and retry
-        match request.response(&mut self.inner).await {
-            Err(_) => {
-                self.user_login().await.context(
-                    "Failed to ensure valid user login before retrying add feedback request",
-                )?;
-                request.response(&mut self.inner).await
-            }
-            res => res,
-        }
-        .map_err(anyhow::Error::from)
+        // Not re-sent on failure: the first attempt may have created the station
+        // already.  An expired session is logged in again, and retried, by the task.
+        request
+            .response(&mut self.inner)
+            .await
+            .map_err(anyhow::Error::from)
     }
 
     pub async fn create_station_from_track_artist(
@@ -327,17 +531,11 @@ impl PandoraSession {
     ) -> Result<CreateStationResponse> {
         trace!("createStation()");
         let request = CreateStation::new_from_track_artist(track_token);
-        // Catch request errors, reconnect, and retry
-        match request.response(&mut self.inner).await {
-            Err(_) => {
-                self.user_login().await.context(
-                    "Failed to ensure valid user login before retrying add feedback request",
-                )?;
-                request.response(&mut self.inner).await
-            }
-            res => res,
-        }
-        .map_err(anyhow::Error::from)
+        // Not re-sent on failure, since the station may already have been created
+        request
+            .response(&mut self.inner)
+            .await
+            .map_err(anyhow::Error::from)
     }
 
     pub async fn create_station_from_music_token(
@@ -346,17 +544,11 @@ impl PandoraSession {
     ) -> Result<CreateStationResponse> {
         trace!("createStation()");
         let request = CreateStation::new_from_music_token(music_token);
-        // Catch request errors, reconnect, and retry
-        match request.response(&mut self.inner).await {
-            Err(_) => {
-                self.user_login().await.context(
-                    "Failed to ensure valid user login before retrying add feedback request",
-                )?;
-                request.response(&mut self.inner).await
-            }
-            res => res,
-        }
-        .map_err(anyhow::Error::from)
+        // Not re-sent on failure, since the station may already have been created
+        request
+            .response(&mut self.inner)
+            .await
+            .map_err(anyhow::Error::from)
     }
 
     pub async fn delete_station(&mut self, station_token: &str) -> Result<()> {
@@ -453,35 +645,27 @@ impl PandoraSession {
         &mut self,
         station_token: &str,
     ) -> Result<crate::pandora::StationSeedsData> {
-        use crate::pandora::{ArtistSeedInfo, SongSeedInfo, StationSeedsData};
         let resp = self.get_station(station_token, true).await?;
-        let music = resp.music.unwrap_or(StationSeeds {
-            songs: vec![],
-            artists: vec![],
-            genres: vec![],
-        });
-        let artist_seeds = music
-            .artists
-            .into_iter()
-            .map(|a| ArtistSeedInfo {
-                seed_id: a.seed_id,
-                music_token: a.music_token,
-                artist_name: a.artist_name,
-            })
-            .collect();
-        let song_seeds = music
-            .songs
-            .into_iter()
-            .map(|s| SongSeedInfo {
-                seed_id: s.seed_id,
-                music_token: s.music_token,
-                song_name: s.song_name,
-                artist_name: s.artist_name,
-            })
-            .collect();
-        Ok(StationSeedsData {
-            artist_seeds,
-            song_seeds,
+        Ok(station_seeds(resp.music))
+    }
+
+    /// Get a station's attributes, seeds and rating totals.  Uses extended
+    /// station attributes.
+    pub async fn get_station_details(
+        &mut self,
+        station_token: &str,
+    ) -> Result<crate::pandora::StationDetails> {
+        let resp = self.get_station(station_token, true).await?;
+        let station = station_info(station_token.to_string(), resp.station_name, &resp.optional);
+        let (thumbs_up, thumbs_down) = resp
+            .feedback
+            .map(|f| (f.total_thumbs_up, f.total_thumbs_down))
+            .unwrap_or_default();
+        Ok(crate::pandora::StationDetails {
+            station,
+            seeds: station_seeds(resp.music),
+            thumbs_up,
+            thumbs_down,
         })
     }
 
@@ -552,18 +736,12 @@ impl PandoraSession {
         trace!("shareStation()");
         let mut request = ShareStation::new(station_id, station_token);
         request.emails = emails;
-        // Catch request errors, reconnect, and retry
-        match request.response(&mut self.inner).await {
-            Err(_) => {
-                self.user_login().await.context(
-                    "Failed to ensure valid user login before retrying add feedback request",
-                )?;
-                request.response(&mut self.inner).await
-            }
-            res => res,
-        }
-        .map(|_: ShareStationResponse| ())
-        .map_err(anyhow::Error::from)
+        // Not re-sent on failure, since the invitations may already have gone out
+        request
+            .response(&mut self.inner)
+            .await
+            .map(|_: ShareStationResponse| ())
+            .map_err(anyhow::Error::from)
     }
 
     pub async fn transform_shared_station(&mut self, station_token: &str) -> Result<()> {
@@ -624,6 +802,56 @@ impl PandoraSession {
             .map_err(anyhow::Error::from)
     }
 
+    pub async fn add_song_bookmark(&mut self, track_token: &str) -> Result<()> {
+        trace!("addSongBookmark()");
+        let request = AddSongBookmark::from(&track_token);
+        // Not re-sent on failure, since the bookmark may already have been made
+        request
+            .response(&mut self.inner)
+            .await
+            .map(|_: AddSongBookmarkResponse| ())
+            .map_err(anyhow::Error::from)
+    }
+
+    /// Shelve a track's song ("tired of this song") for a month, without rating it.
+    pub async fn sleep_song(&mut self, track_token: &str) -> Result<()> {
+        trace!("sleepSong()");
+        let request = SleepSong::from(&track_token);
+        // Sent once; the task decides whether a failure is worth retrying
+        request
+            .response(&mut self.inner)
+            .await
+            .map(|_: SleepSongResponse| ())
+            .map_err(anyhow::Error::from)
+    }
+
+    pub async fn add_artist_bookmark(&mut self, track_token: &str) -> Result<()> {
+        trace!("addArtistBookmark()");
+        let request = AddArtistBookmark::from(&track_token);
+        // Not re-sent on failure, since the bookmark may already have been made
+        request
+            .response(&mut self.inner)
+            .await
+            .map(|_: AddArtistBookmarkResponse| ())
+            .map_err(anyhow::Error::from)
+    }
+
+    /// Get the bookmarked artists and songs, artists first.
+    pub async fn get_bookmarked_music(&mut self) -> Result<Vec<crate::pandora::MusicMatch>> {
+        use crate::pandora::MusicMatch;
+        let resp = self.get_bookmarks().await?;
+        let artists = resp.artists.into_iter().map(|a| MusicMatch::Artist {
+            artist_name: a.artist_name,
+            music_token: a.music_token,
+        });
+        let songs = resp.songs.into_iter().map(|s| MusicMatch::Song {
+            song_name: s.song_name,
+            artist_name: s.artist_name,
+            music_token: s.music_token,
+        });
+        Ok(artists.chain(songs).collect())
+    }
+
     pub async fn get_bookmarks(&mut self) -> Result<GetBookmarksResponse> {
         trace!("getBookmarks()");
         let request = GetBookmarks::new();
@@ -661,7 +889,7 @@ impl PandoraSession {
         trace!("getStationList()");
         let request = GetStationList::new();
         // Catch request errors, reconnect, and retry
-        match request.response(&mut self.inner).await {
+        let resp: GetStationListResponse = match request.response(&mut self.inner).await {
             Err(_) => {
                 self.user_login().await.context(
                     "Failed to ensure valid user login before retrying add feedback request",
@@ -670,7 +898,31 @@ impl PandoraSession {
             }
             res => res,
         }
-        .map_err(anyhow::Error::from)
+        .map_err(anyhow::Error::from)?;
+        *self
+            .station_list_checksum
+            .lock()
+            .expect("station list checksum lock") = Some(resp.checksum.clone());
+        Ok(resp)
+    }
+
+    /// The audio quality the user prefers for playlist tracks.
+    pub fn audio_quality(&self) -> AudioQuality {
+        self.config
+            .read()
+            .expect("config read for audio quality")
+            .audio_quality()
+    }
+
+    /// Whether the station list has changed since it was last retrieved.
+    pub async fn station_list_changed(&mut self) -> Result<bool> {
+        let checksum = self.get_station_list_checksum().await?;
+        Ok(self
+            .station_list_checksum
+            .lock()
+            .expect("station list checksum lock")
+            .as_ref()
+            != Some(&checksum))
     }
 
     pub async fn get_usage_info(&mut self) -> Result<GetUsageInfoResponse> {
@@ -0,0 +1,135 @@
+//! How long to wait before retrying Pandora commands that failed in a way that
+//! should clear up by itself, and before sending more of them after Pandora
+//! says we're making too many.
+
+use std::time::Duration;
+
+use crate::pandora::PandoraCommand;
+
+/// How long to hold off on a class of commands after Pandora throttles one.
+pub(crate) const THROTTLE_COOLDOWN: Duration = Duration::from_secs(60);
+
+/// Commands that share a retry policy, and are throttled together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum CommandClass {
+    Login,
+    Playlist,
+    StationList,
+    /// Refreshes the task runs on its own, which come around again anyway.
+    Background,
+    /// Changes that can't safely be made twice, which may have gone through
+    /// even though they seem to have failed (e.g. on a read timeout).
+    Unrepeatable,
+    /// Everything else, which the user is waiting on.
+    Interactive,
+}
+
+impl CommandClass {
+    pub(crate) fn of(command: &PandoraCommand) -> Self {
+        match command {
+            PandoraCommand::Connect => Self::Login,
+            PandoraCommand::GetPlaylist(_) => Self::Playlist,
+            PandoraCommand::GetStationList => Self::StationList,
+            PandoraCommand::GetUsageInfo | PandoraCommand::RefreshStationList => Self::Background,
+            PandoraCommand::CreateStationFromTrack { .. }
+            | PandoraCommand::CreateStationFromMusic { .. }
+            | PandoraCommand::AcceptSharedStation(_)
+            | PandoraCommand::ShareStation { .. }
+            | PandoraCommand::AddBookmark { .. }
+            | PandoraCommand::AddSeed { .. }
+            | PandoraCommand::AddArtistSeed { .. } => Self::Unrepeatable,
+            // Shelving a song that is already shelved changes nothing, so a
+            // retry can't do any harm
+            PandoraCommand::SleepSong(_) => Self::Interactive,
+            _ => Self::Interactive,
+        }
+    }
+
+    /// The delay before the given retry (counting from 1), or `None` once
+    /// the retries for this class are used up.  Delays double with each
+    /// retry, up to a limit.
+    pub(crate) fn retry_delay(self, retry: u32) -> Option<Duration> {
+        let (first, limit, retries) = match self {
+            Self::Login => (Duration::from_secs(2), Duration::from_secs(120), 6),
+            Self::Playlist => (Duration::from_secs(5), Duration::from_secs(120), 4),
+            Self::StationList => (Duration::from_secs(2), Duration::from_secs(60), 4),
+            Self::Interactive => (Duration::from_secs(1), Duration::from_secs(8), 2),
+            Self::Background | Self::Unrepeatable => return None,
+        };
+        if retry == 0 || retry > retries {
+            return None;
+        }
+        Some(jitter(first.saturating_mul(1 << (retry - 1)).min(limit)))
+    }
+}
+
+impl std::fmt::Display for CommandClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Login => write!(f, "login"),
+            Self::Playlist => write!(f, "playlist"),
+            Self::StationList => write!(f, "station list"),
+            Self::Background => write!(f, "refresh"),
+            Self::Interactive | Self::Unrepeatable => write!(f, "request"),
+        }
+    }
+}
+
+/// Spread a delay by up to a quarter either way, so that clients which failed
+/// together don't all come back at the same moment.
+pub(crate) fn jitter(delay: Duration) -> Duration {
+    let quarter = delay / 4;
+    delay - quarter + quarter.mul_f64(2.0 * fastrand::f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delays_grow_within_jitter() {
+        let delays: Vec<Duration> = (1..=4)
+            .map(|retry| {
+                CommandClass::Playlist
+                    .retry_delay(retry)
+                    .expect("playlist retry")
+            })
+            .collect();
+        for (delay, expected) in delays.iter().zip([5, 10, 20, 40]) {
+            let expected = Duration::from_secs(expected);
+            assert!(
+                *delay >= expected * 3 / 4 && *delay <= expected * 5 / 4,
+                "{delay:?} not within jitter of {expected:?}"
+            );
+        }
+        assert_eq!(CommandClass::Playlist.retry_delay(5), None);
+    }
+
+    #[test]
+    fn retry_delays_are_limited() {
+        let delay = CommandClass::Login.retry_delay(6).expect("login retry");
+        assert!(delay <= Duration::from_secs(150), "{delay:?} over limit");
+    }
+
+    #[test]
+    fn background_refreshes_are_not_retried() {
+        assert_eq!(CommandClass::Background.retry_delay(1), None);
+    }
+
+    #[test]
+    fn unrepeatable_changes_are_not_retried() {
+        let share = PandoraCommand::ShareStation {
+            station_id: String::from("101"),
+            emails: vec![String::from("friend@example.com")],
+        };
+        assert_eq!(CommandClass::of(&share), CommandClass::Unrepeatable);
+        assert_eq!(CommandClass::of(&share).retry_delay(1), None);
+    }
+
+    #[test]
+    fn sleeping_a_song_is_retried() {
+        let sleep = PandoraCommand::SleepSong(String::from("track"));
+        assert_eq!(CommandClass::of(&sleep), CommandClass::Interactive);
+        assert!(CommandClass::of(&sleep).retry_delay(1).is_some());
+    }
+}
@@ -0,0 +1,117 @@
+//! Genre station categories, cached on disk between runs.
+//! The list is large and rarely changes, so it's only downloaded again when
+//! Pandora reports a different checksum for it.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use log::{debug, trace, warn};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::pandora::api::PandoraSession;
+use crate::track::app_cache_dir;
+
+/// A station in a genre category. The station token is used as the music
+/// token when creating a station from it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct GenreStation {
+    pub station_token: String,
+    pub station_name: String,
+}
+
+/// A named category of genre stations.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct GenreCategory {
+    pub category_name: String,
+    pub stations: Vec<GenreStation>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct GenreCache {
+    checksum: String,
+    categories: Vec<GenreCategory>,
+}
+
+fn genre_cache_path() -> Result<PathBuf> {
+    Ok(app_cache_dir()
+        .context("Failed to determine the correct application cache directory for this platform")?
+        .join("genre_stations.json"))
+}
+
+async fn read_genre_cache() -> Result<GenreCache> {
+    let path = genre_cache_path()?;
+    let data = tokio::fs::read(&path)
+        .await
+        .with_context(|| format!("Failed reading genre cache at {}", path.display()))?;
+    serde_json::from_slice(&data)
+        .with_context(|| format!("Error parsing genre cache at {}", path.display()))
+}
+
+/// Write the cache to a temporary file, then move it into place, so that
+/// concurrent writers and readers never see a partly written cache.
+async fn write_genre_cache(cache: &GenreCache) -> Result<()> {
+    let path = genre_cache_path()?;
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await.with_context(|| {
+            format!(
+                "Failed to create directory for genre cache at {}",
+                dir.display()
+            )
+        })?;
+    }
+    let data = serde_json::to_vec(cache)
+        .with_context(|| format!("Failed serializing genre cache to {}", path.display()))?;
+    let tmp_path = path.with_extension(format!("json.{:016x}.tmp", fastrand::u64(..)));
+    tokio::fs::write(&tmp_path, data)
+        .await
+        .with_context(|| format!("Failed writing to genre cache at {}", tmp_path.display()))?;
+    if let Err(e) = tokio::fs::rename(&tmp_path, &path).await {
+        let _ = tokio::fs::remove_file(&tmp_path).await;
+        return Err(e).with_context(|| {
+            format!("Failed moving genre cache into place at {}", path.display())
+        });
+    }
+    Ok(())
+}
+
+/// Get the genre station categories, from the on-disk cache if it is still
+/// current, otherwise from Pandora (updating the cache).
+pub(crate) async fn get_genre_categories(
+    session: &mut PandoraSession,
+) -> Result<Vec<GenreCategory>> {
+    let checksum = session.get_genre_stations_checksum().await?;
+    match read_genre_cache().await {
+        Ok(cache) if cache.checksum == checksum => {
+            debug!("Using cached genre stations (checksum {checksum})");
+            return Ok(cache.categories);
+        }
+        Ok(_) => debug!("Genre stations changed, downloading them again"),
+        Err(e) => trace!("No usable genre station cache: {e:#}"),
+    }
+
+    let categories: Vec<GenreCategory> = session
+        .get_genre_stations()
+        .await?
+        .into_iter()
+        .map(|c| GenreCategory {
+            category_name: c.category_name,
+            stations: c
+                .stations
+                .into_iter()
+                .map(|s| GenreStation {
+                    station_token: s.station_token,
+                    station_name: s.station_name,
+                })
+                .collect(),
+        })
+        .collect();
+    let cache = GenreCache {
+        checksum,
+        categories,
+    };
+    if let Err(e) = write_genre_cache(&cache).await {
+        // Not fatal, we'll just download them again next time
+        warn!("Failed to cache genre stations: {e:#}");
+    }
+    Ok(cache.categories)
+}
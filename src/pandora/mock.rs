@@ -0,0 +1,819 @@
+//! A mock of Pandora's tuner JSON API, for running the Pandora task, model and
+//! track fetcher end-to-end without a Pandora account or network access.
+//!
+//! It speaks just enough of the protocol used by `pandora_api` (blowfish
+//! encrypted request bodies, and an encrypted sync time from partner login)
+//! to log in and serve a station list, playlists, seeds and feedback.  The
+//! audio URLs in playlists point back at the mock, which serves a small m4a
+//! fixture for them.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use blowfish::Blowfish;
+use cipher::{BlockDecrypt, BlockEncrypt, KeyInit};
+use log::{debug, trace, warn};
+use pandora_api::json::station::PlaylistTrack;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::config::{Config, Credentials, PartnerProfile, SharedConfig};
+use crate::track::Track;
+
+pub(crate) const USERNAME: &str = "listener@example.com";
+pub(crate) const PASSWORD: &str = "correct horse battery staple";
+
+/// Audio served for every track in a playlist.
+const AUDIO_FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/silence.m4a");
+
+// Error codes from the Pandora JSON API
+const INTERNAL: u32 = 0;
+const INVALID_AUTH_TOKEN: u32 = 1001;
+const INVALID_LOGIN: u32 = 1002;
+const STATION_DOES_NOT_EXIST: u32 = 1006;
+const PLAYLIST_EXCEEDED: u32 = 1039;
+
+/// Number of tracks returned for each playlist request.
+const PLAYLIST_LEN: usize = 4;
+
+#[derive(Debug, Clone)]
+struct MockStation {
+    id: String,
+    name: String,
+    quickmix: bool,
+    seeds: Vec<MockSeed>,
+}
+
+#[derive(Debug, Clone)]
+struct MockSeed {
+    seed_id: String,
+    music_token: String,
+    artist_name: String,
+    song_name: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct MockTrack {
+    track_token: String,
+    music_id: String,
+    music_token: String,
+    station_id: String,
+    song_name: String,
+}
+
+#[derive(Debug, Clone)]
+struct MockFeedback {
+    feedback_id: String,
+    station_id: String,
+    music_token: String,
+    song_name: String,
+    is_positive: bool,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    next_id: u64,
+    partner_tokens: Vec<String>,
+    user_tokens: Vec<String>,
+    stations: Vec<MockStation>,
+    quickmix_ids: Vec<String>,
+    tracks: HashMap<String, MockTrack>,
+    feedback: Vec<MockFeedback>,
+    /// Bumped on every change to the station list, and used as its checksum.
+    station_list_version: u64,
+    high_quality_missing: bool,
+    /// How long to stall each playlist request before answering it.
+    playlist_delay: Option<Duration>,
+    playlists_throttled: bool,
+}
+
+impl MockState {
+    fn new() -> Self {
+        Self {
+            stations: vec![
+                MockStation {
+                    id: String::from("100"),
+                    name: String::from("QuickMix"),
+                    quickmix: true,
+                    seeds: Vec::new(),
+                },
+                MockStation {
+                    id: String::from("101"),
+                    name: String::from("Mock Rock Radio"),
+                    quickmix: false,
+                    seeds: vec![MockSeed {
+                        seed_id: String::from("seed-1"),
+                        music_token: String::from("R1"),
+                        artist_name: String::from("Mock Artist"),
+                        song_name: None,
+                    }],
+                },
+                MockStation {
+                    id: String::from("102"),
+                    name: String::from("Mock Jazz Radio"),
+                    quickmix: false,
+                    seeds: Vec::new(),
+                },
+            ],
+            quickmix_ids: vec![String::from("101")],
+            ..Self::default()
+        }
+    }
+
+    fn next_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    fn station(&self, id: &str) -> Option<&MockStation> {
+        self.stations.iter().find(|s| s.id == id)
+    }
+
+    fn song_rating(&self, station_id: &str, music_token: &str) -> u32 {
+        self.feedback
+            .iter()
+            .find(|f| f.station_id == station_id && f.music_token == music_token)
+            .map(|f| u32::from(f.is_positive))
+            .unwrap_or(0)
+    }
+}
+
+/// A mock Pandora service listening on a local port.  It runs until the
+/// runtime it was started on shuts down.
+#[derive(Debug, Clone)]
+pub(crate) struct MockPandora {
+    addr: SocketAddr,
+    partner: PartnerProfile,
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockPandora {
+    pub(crate) async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind mock pandora listener");
+        let addr = listener
+            .local_addr()
+            .expect("mock pandora listener address");
+        let partner = PartnerProfile {
+            endpoint: format!("http://{addr}/services/json/"),
+            ..PartnerProfile::default()
+        };
+        let mock = Self {
+            addr,
+            partner,
+            state: Arc::new(Mutex::new(MockState::new())),
+        };
+        let server = mock.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let server = server.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = server.serve_connection(stream).await {
+                        warn!("Mock pandora connection failed: {e:#}");
+                    }
+                });
+            }
+        });
+        debug!("Mock pandora listening on {addr}");
+        mock
+    }
+
+    /// A config that logs in to this mock with the right credentials.
+    pub(crate) fn config(&self) -> SharedConfig {
+        let config = Config {
+            login: Credentials::ConfigFile(USERNAME.to_string(), PASSWORD.to_string()),
+            partner: self.partner.clone(),
+            ..Config::default()
+        };
+        Arc::new(RwLock::new(config))
+    }
+
+    /// Add a station, as if it were created from another device.
+    pub(crate) fn add_station(&self, name: &str) -> String {
+        let mut state = self.state.lock().expect("mock state lock");
+        let id = (200 + state.next_id()).to_string();
+        state.stations.push(MockStation {
+            id: id.clone(),
+            name: name.to_string(),
+            quickmix: false,
+            seeds: Vec::new(),
+        });
+        state.station_list_version += 1;
+        id
+    }
+
+    /// Invalidate all partner and user auth tokens, as Pandora does when a
+    /// session has been idle too long.
+    pub(crate) fn expire_sessions(&self) {
+        let mut state = self.state.lock().expect("mock state lock");
+        state.partner_tokens.clear();
+        state.user_tokens.clear();
+    }
+
+    /// Make the high quality audio URL of each new track fail to download.
+    pub(crate) fn set_high_quality_missing(&self, missing: bool) {
+        self.state
+            .lock()
+            .expect("mock state lock")
+            .high_quality_missing = missing;
+    }
+
+    /// Stall every playlist request for the given time before answering it.
+    pub(crate) fn set_playlist_delay(&self, delay: Option<Duration>) {
+        self.state.lock().expect("mock state lock").playlist_delay = delay;
+    }
+
+    /// Refuse playlist requests as being too frequent.
+    pub(crate) fn set_playlists_throttled(&self, throttled: bool) {
+        self.state
+            .lock()
+            .expect("mock state lock")
+            .playlists_throttled = throttled;
+    }
+
+    /// A track from a fresh playlist for the given station, cached under
+    /// `cache_dir` instead of the user's cache directory.
+    pub(crate) fn track(&self, station_id: &str, cache_dir: &Path) -> Track {
+        let items = {
+            let mut state = self.state.lock().expect("mock state lock");
+            playlist_items(&mut state, self.addr, station_id)
+        };
+        let pl_track: PlaylistTrack =
+            serde_json::from_value(items[0].clone()).expect("mock playlist track");
+        let mut track = Track::try_from(pl_track).expect("mock playlist track conversion");
+        track.cache_path = cache_dir.join(format!("{}.m4a", track.track_token));
+        track
+    }
+
+    async fn serve_connection(&self, stream: TcpStream) -> anyhow::Result<()> {
+        let mut reader = BufReader::new(stream);
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).await? == 0 {
+            return Ok(());
+        }
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).await? == 0 {
+                break;
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse()?;
+                }
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).await?;
+
+        let target = request_line.split_whitespace().nth(1).unwrap_or("/");
+        trace!("Mock pandora request {}", request_line.trim_end());
+        let (status, content_type, payload) = if target.starts_with("/audio/") {
+            match tokio::fs::read(AUDIO_FIXTURE).await {
+                Ok(audio) => ("200 OK", "audio/mp4", audio),
+                Err(e) => {
+                    warn!("Mock pandora failed reading {AUDIO_FIXTURE}: {e:#}");
+                    ("500 Internal Server Error", "text/plain", Vec::new())
+                }
+            }
+        } else if target.starts_with("/services/json") {
+            let query = parse_query(target);
+            if query.get("method").map(String::as_str) == Some("station.getPlaylist") {
+                let delay = self.state.lock().expect("mock state lock").playlist_delay;
+                if let Some(delay) = delay {
+                    tokio::time::sleep(delay).await;
+                }
+            }
+            let response = self.handle_api(&query, &body);
+            (
+                "200 OK",
+                "application/json",
+                response.to_string().into_bytes(),
+            )
+        } else {
+            ("404 Not Found", "text/plain", Vec::new())
+        };
+
+        let mut stream = reader.into_inner();
+        let headers = format!(
+            "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            payload.len()
+        );
+        stream.write_all(headers.as_bytes()).await?;
+        stream.write_all(&payload).await?;
+        stream.shutdown().await?;
+        Ok(())
+    }
+
+    fn handle_api(&self, query: &HashMap<String, String>, body: &[u8]) -> Value {
+        let method = query.get("method").map(String::as_str).unwrap_or_default();
+        // Partner login is the only request sent in the clear
+        let request: Value = if method == "auth.partnerLogin" {
+            serde_json::from_slice(body).unwrap_or_default()
+        } else {
+            std::str::from_utf8(body)
+                .ok()
+                .and_then(from_hex)
+                .map(|data| decrypt(&self.partner.encrypt_key, &data))
+                .and_then(|data| serde_json::from_slice(&data).ok())
+                .unwrap_or_default()
+        };
+        trace!("Mock pandora {method}: {request}");
+
+        let mut state = self.state.lock().expect("mock state lock");
+        match method {
+            "auth.partnerLogin" => self.partner_login(&mut state, &request),
+            "auth.userLogin" => user_login(&mut state, &request),
+            _ if !str_field(&request, "userAuthToken")
+                .is_some_and(|t| state.user_tokens.iter().any(|u| u == t)) =>
+            {
+                fail(INVALID_AUTH_TOKEN, "An unexpected error occurred")
+            }
+            "user.getStationList" => ok(json!({
+                "stations": state
+                    .stations
+                    .iter()
+                    .map(|s| station_json(s, &state.quickmix_ids))
+                    .collect::<Vec<_>>(),
+                "checksum": state.station_list_version.to_string(),
+            })),
+            "user.getStationListChecksum" => ok(json!({
+                "checksum": state.station_list_version.to_string(),
+            })),
+            "user.setQuickMix" => {
+                state.quickmix_ids = request["quickMixStationIds"]
+                    .as_array()
+                    .map(|ids| {
+                        ids.iter()
+                            .filter_map(|id| id.as_str().map(String::from))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                state.station_list_version += 1;
+                ok(json!({}))
+            }
+            "user.sleepSong" => ok(json!({})),
+            "user.getUsageInfo" => ok(json!({
+                "accountMonthlyListening": 12,
+                "deviceMonthlyListening": 12,
+                "monthlyCapHours": 40,
+                "monthlyCapWarningPercent": 85,
+                "monthlyCapWarningRepeatPercent": 95,
+                "isMonthlyPayer": false,
+                "isCapped": false,
+                "listeningTimestamp": 0,
+            })),
+            "station.getPlaylist" => {
+                let station_id = str_field(&request, "stationToken").unwrap_or_default();
+                if state.station(station_id).is_none() {
+                    return fail(STATION_DOES_NOT_EXIST, "Station does not exist");
+                }
+                if state.playlists_throttled {
+                    return fail(PLAYLIST_EXCEEDED, "Too many requests for a new playlist");
+                }
+                let items = playlist_items(&mut state, self.addr, station_id);
+                ok(json!({ "items": items }))
+            }
+            "station.getStation" => {
+                let station_id = str_field(&request, "stationToken").unwrap_or_default();
+                let extended = request["includeExtendedAttributes"]
+                    .as_bool()
+                    .unwrap_or(false);
+                match state.station(station_id) {
+                    Some(station) => {
+                        let mut resp = station_json(station, &state.quickmix_ids);
+                        if extended {
+                            resp["music"] = seeds_json(station);
+                            resp["feedback"] = feedback_list_json(&state, station_id);
+                        }
+                        ok(resp)
+                    }
+                    None => fail(STATION_DOES_NOT_EXIST, "Station does not exist"),
+                }
+            }
+            "station.addFeedback" => {
+                let track_token = str_field(&request, "trackToken").unwrap_or_default();
+                let is_positive = request["isPositive"].as_bool().unwrap_or(false);
+                let Some(track) = state.tracks.get(track_token).cloned() else {
+                    return fail(INTERNAL, "Unknown track");
+                };
+                state.feedback.retain(|f| {
+                    f.station_id != track.station_id || f.music_token != track.music_token
+                });
+                let feedback = MockFeedback {
+                    feedback_id: format!("feedback-{}", state.next_id()),
+                    station_id: track.station_id.clone(),
+                    music_token: track.music_token.clone(),
+                    song_name: track.song_name.clone(),
+                    is_positive,
+                };
+                let mut resp = feedback_json(&feedback);
+                state.feedback.push(feedback);
+                let (up, down) = feedback_totals(&state, &track.station_id);
+                resp["totalThumbsUp"] = json!(up);
+                resp["totalThumbsDown"] = json!(down);
+                ok(resp)
+            }
+            "station.deleteFeedback" => {
+                let feedback_id = str_field(&request, "feedbackId").unwrap_or_default();
+                state.feedback.retain(|f| f.feedback_id != feedback_id);
+                ok(json!({}))
+            }
+            "station.addMusic" => {
+                let station_id = str_field(&request, "stationToken").unwrap_or_default();
+                let music_token = str_field(&request, "musicToken").unwrap_or_default();
+                let seed = MockSeed {
+                    seed_id: format!("seed-{}", state.next_id()),
+                    music_token: music_token.to_string(),
+                    artist_name: String::from("Mock Artist"),
+                    song_name: music_token
+                        .starts_with('S')
+                        .then(|| format!("Mock Song {music_token}")),
+                };
+                let resp = seed_json(&seed);
+                match state.stations.iter_mut().find(|s| s.id == station_id) {
+                    Some(station) => {
+                        station.seeds.push(seed);
+                        ok(resp)
+                    }
+                    None => fail(STATION_DOES_NOT_EXIST, "Station does not exist"),
+                }
+            }
+            "station.deleteMusic" => {
+                let seed_id = str_field(&request, "seedId").unwrap_or_default();
+                for station in state.stations.iter_mut() {
+                    station.seeds.retain(|s| s.seed_id != seed_id);
+                }
+                ok(json!({}))
+            }
+            "track.getTrack" => {
+                let token = str_field(&request, "trackToken")
+                    .or_else(|| str_field(&request, "musicId"))
+                    .unwrap_or_default();
+                match state
+                    .tracks
+                    .values()
+                    .find(|t| t.track_token == token || t.music_id == token)
+                {
+                    Some(track) => ok(json!({
+                        "trackToken": track.track_token,
+                        "musicId": track.music_id,
+                        "musicToken": track.music_token,
+                        "songName": track.song_name,
+                        "artistName": "Mock Artist",
+                        "albumName": "Mock Album",
+                        "albumArtUrl": "",
+                        "songDetailUrl": "",
+                    })),
+                    None => fail(INTERNAL, "Unknown track"),
+                }
+            }
+            _ => fail(INTERNAL, &format!("Unsupported method {method}")),
+        }
+    }
+
+    fn partner_login(&self, state: &mut MockState, request: &Value) -> Value {
+        if str_field(request, "username") != Some(self.partner.username.as_str())
+            || str_field(request, "password") != Some(self.partner.password.as_str())
+        {
+            return fail(INVALID_LOGIN, "Invalid partner login");
+        }
+        let token = format!("partner-token-{}", state.next_id());
+        state.partner_tokens.push(token.clone());
+        // Four bytes of junk, then the server time in seconds
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let sync_time = format!("junk{now}");
+        ok(json!({
+            "partnerId": "42",
+            "partnerAuthToken": token,
+            "syncTime": to_hex(&encrypt(&self.partner.decrypt_key, sync_time.as_bytes())),
+            "stationSkipLimit": 6,
+            "stationSkipUnit": "hour",
+            "deviceProperties": {
+                "videoAdRefreshInterval": 900,
+                "videoAdUniqueInterval": 0,
+                "adRefreshInterval": 5,
+                "videoAdStartInterval": 180,
+            },
+            "urls": {
+                "autoComplete": "",
+            },
+        }))
+    }
+}
+
+fn user_login(state: &mut MockState, request: &Value) -> Value {
+    if !str_field(request, "partnerAuthToken")
+        .is_some_and(|t| state.partner_tokens.iter().any(|p| p == t))
+    {
+        return fail(INVALID_AUTH_TOKEN, "An unexpected error occurred");
+    }
+    if str_field(request, "username") != Some(USERNAME)
+        || str_field(request, "password") != Some(PASSWORD)
+    {
+        return fail(INVALID_LOGIN, "Invalid username or password");
+    }
+    let token = format!("user-token-{}", state.next_id());
+    state.user_tokens.push(token.clone());
+    ok(json!({
+        "userId": "7",
+        "userAuthToken": token,
+        "username": USERNAME,
+        "canListen": true,
+        "hasAudioAds": false,
+        "isCapped": false,
+        "listeningTimeoutMinutes": "180",
+        "listeningTimeoutAlertMsgUri": "",
+        "maxStationsAllowed": 100,
+        "minimumAdRefreshInterval": 5,
+        "splashScreenAdUrl": "",
+        "videoAdUrl": "",
+        "stationCreationAdUrl": "",
+        "userProfileUrl": "",
+        "webname": "listener",
+    }))
+}
+
+fn playlist_items(state: &mut MockState, addr: SocketAddr, station_id: &str) -> Vec<Value> {
+    (0..PLAYLIST_LEN)
+        .map(|_| {
+            let n = state.next_id();
+            let track = MockTrack {
+                track_token: format!("track-{n}"),
+                music_id: format!("M{n}"),
+                music_token: format!("S{n}"),
+                station_id: station_id.to_string(),
+                song_name: format!("Mock Song {n}"),
+            };
+            let high_url = if state.high_quality_missing {
+                format!("http://{addr}/missing/{}.m4a", track.track_token)
+            } else {
+                format!("http://{addr}/audio/{}-high.m4a", track.track_token)
+            };
+            let item = json!({
+                "trackToken": track.track_token,
+                "musicId": track.music_id,
+                "stationId": track.station_id,
+                "audioUrlMap": {
+                    "highQuality": audio_stream_json(&high_url, "192"),
+                    "mediumQuality": audio_stream_json(
+                        &format!("http://{addr}/audio/{}-medium.m4a", track.track_token),
+                        "64",
+                    ),
+                    "lowQuality": audio_stream_json(
+                        &format!("http://{addr}/audio/{}-low.m4a", track.track_token),
+                        "32",
+                    ),
+                },
+                "artistName": "Mock Artist",
+                "albumName": "Mock Album",
+                "songName": track.song_name,
+                "songRating": state.song_rating(station_id, &track.music_token),
+                "songIdentity": track.music_token,
+                "trackLength": 1,
+                "trackGain": "-4.59",
+                "allowFeedback": true,
+                "albumArtUrl": "",
+                "artistDetailUrl": "",
+                "albumDetailUrl": "",
+                "songDetailUrl": "",
+                "songExplorerUrl": "",
+                "albumExplorerUrl": "",
+                "itunesSongUrl": "",
+                "amazonAlbumUrl": "",
+                "amazonAlbumAsin": "",
+                "amazonAlbumDigitalAsin": "",
+                "amazonSongDigitalAsin": "",
+            });
+            state.tracks.insert(track.track_token.clone(), track);
+            item
+        })
+        .collect()
+}
+
+fn audio_stream_json(url: &str, bitrate: &str) -> Value {
+    json!({
+        "bitrate": bitrate,
+        "encoding": "aacplus",
+        "audioUrl": url,
+        "protocol": "http",
+    })
+}
+
+fn date_created_json() -> Value {
+    json!({
+        "date": 14,
+        "day": 2,
+        "hours": 22,
+        "minutes": 13,
+        "month": 10,
+        "nanos": 0,
+        "seconds": 20,
+        "time": 1700000000000u64,
+        "timezoneOffset": 0,
+        "year": 123,
+    })
+}
+
+fn station_json(station: &MockStation, quickmix_ids: &[String]) -> Value {
+    let mut resp = json!({
+        "stationId": station.id,
+        "stationToken": station.id,
+        "stationName": station.name,
+        "isShared": false,
+        "isQuickMix": station.quickmix,
+        "allowDelete": !station.quickmix,
+        "allowRename": !station.quickmix,
+        "allowAddMusic": !station.quickmix,
+        "requiresCleanAds": false,
+        "suppressVideoAds": true,
+        "stationDetailUrl": "",
+        "stationSharingUrl": "",
+        "artUrl": "",
+        "genre": ["Mock Genre"],
+        "dateCreated": date_created_json(),
+    });
+    if station.quickmix {
+        resp["quickMixStationIds"] = json!(quickmix_ids);
+    }
+    resp
+}
+
+fn seed_json(seed: &MockSeed) -> Value {
+    let mut resp = json!({
+        "seedId": seed.seed_id,
+        "musicToken": seed.music_token,
+        "artistName": seed.artist_name,
+        "artUrl": "",
+        "dateCreated": date_created_json(),
+    });
+    if let Some(song_name) = &seed.song_name {
+        resp["songName"] = json!(song_name);
+    }
+    resp
+}
+
+fn seeds_json(station: &MockStation) -> Value {
+    let (songs, artists): (Vec<&MockSeed>, Vec<&MockSeed>) =
+        station.seeds.iter().partition(|s| s.song_name.is_some());
+    json!({
+        "songs": songs.into_iter().map(seed_json).collect::<Vec<_>>(),
+        "artists": artists.into_iter().map(seed_json).collect::<Vec<_>>(),
+        "genres": [],
+    })
+}
+
+fn feedback_json(feedback: &MockFeedback) -> Value {
+    json!({
+        "feedbackId": feedback.feedback_id,
+        "musicToken": feedback.music_token,
+        "songIdentity": feedback.music_token,
+        "songName": feedback.song_name,
+        "artistName": "Mock Artist",
+        "albumArtUrl": "",
+        "isPositive": feedback.is_positive,
+        "dateCreated": date_created_json(),
+    })
+}
+
+fn feedback_totals(state: &MockState, station_id: &str) -> (usize, usize) {
+    let station_feedback = state.feedback.iter().filter(|f| f.station_id == station_id);
+    let up = station_feedback.clone().filter(|f| f.is_positive).count();
+    let down = station_feedback.filter(|f| !f.is_positive).count();
+    (up, down)
+}
+
+fn feedback_list_json(state: &MockState, station_id: &str) -> Value {
+    let (up, down) = feedback_totals(state, station_id);
+    let list = |positive: bool| {
+        state
+            .feedback
+            .iter()
+            .filter(|f| f.station_id == station_id && f.is_positive == positive)
+            .map(feedback_json)
+            .collect::<Vec<_>>()
+    };
+    json!({
+        "thumbsUp": list(true),
+        "totalThumbsUp": up,
+        "thumbsDown": list(false),
+        "totalThumbsDown": down,
+    })
+}
+
+fn ok(result: Value) -> Value {
+    json!({ "stat": "ok", "result": result })
+}
+
+fn fail(code: u32, message: &str) -> Value {
+    json!({ "stat": "fail", "message": message, "code": code })
+}
+
+fn str_field<'a>(request: &'a Value, name: &str) -> Option<&'a str> {
+    request.get(name).and_then(Value::as_str)
+}
+
+fn parse_query(target: &str) -> HashMap<String, String> {
+    target
+        .split_once('?')
+        .map(|(_, query)| query)
+        .unwrap_or_default()
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(k, v)| (percent_decode(k), percent_decode(v)))
+        .collect()
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            // An escape needs both hex digits, even at the very end
+            b'%' if i + 3 <= bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+                match u8::from_str_radix(hex, 16) {
+                    Ok(b) => {
+                        decoded.push(b);
+                        i += 3;
+                        continue;
+                    }
+                    Err(_) => decoded.push(b'%'),
+                }
+            }
+            b'+' => decoded.push(b' '),
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn blowfish(key: &str) -> Blowfish {
+    Blowfish::new_from_slice(key.as_bytes()).expect("valid blowfish key")
+}
+
+/// Blowfish ECB, zero-padded to the block size, as Pandora does it.
+fn encrypt(key: &str, data: &[u8]) -> Vec<u8> {
+    let cipher = blowfish(key);
+    let mut data = data.to_vec();
+    data.resize(data.len().div_ceil(8) * 8, 0);
+    for block in data.chunks_mut(8) {
+        cipher.encrypt_block(cipher::Block::<Blowfish>::from_mut_slice(block));
+    }
+    data
+}
+
+fn decrypt(key: &str, data: &[u8]) -> Vec<u8> {
+    let cipher = blowfish(key);
+    let mut data = data.to_vec();
+    data.truncate(data.len() / 8 * 8);
+    for block in data.chunks_mut(8) {
+        cipher.decrypt_block(cipher::Block::<Blowfish>::from_mut_slice(block));
+    }
+    while data.last() == Some(&0) {
+        data.pop();
+    }
+    data
+}
+
+fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim();
+    if text.len() % 2 != 0 {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent_decode_escapes() {
+        assert_eq!(percent_decode("a%20b+c"), "a b c");
+        assert_eq!(percent_decode("ends%40"), "ends@");
+        assert_eq!(percent_decode("short%4"), "short%4");
+    }
+}
@@ -1,28 +1,48 @@
 //! Dedicated subsystem and task for Pandora API requests.
 //! The model sends commands and receives results asynchronously so it can keep processing
-//! UI/player requests while network I/O runs here.
+//! UI/player requests while network I/O runs here.  Commands run concurrently, except that
+//! logging in and out happen one at a time, and commands for a station run in the order sent.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::convert::TryFrom;
+use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use log::{debug, error, trace, warn};
 use tokio::sync::mpsc;
+use tokio::task::{JoinError, JoinSet};
+use tokio::time::Instant;
 
 use crate::config::SharedConfig;
 use crate::errors::Error;
 use crate::pandora::api::PandoraSession;
+use crate::pandora::backoff::{jitter, CommandClass, THROTTLE_COOLDOWN};
 use crate::track::Track;
 
 mod api;
+mod backoff;
+mod genres;
+#[cfg(test)]
+pub(crate) mod mock;
+
+pub(crate) use genres::{GenreCategory, GenreStation};
+
+/// How often the Pandora task refreshes the account usage info while connected.
+const USAGE_REFRESH_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// How often the Pandora task checks whether the station list was changed
+/// elsewhere (e.g. from another device) while connected.
+const STATION_LIST_REFRESH_INTERVAL: Duration = Duration::from_secs(5 * 60);
 
 /// Commands the model sends to the Pandora task.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub(crate) enum PandoraCommand {
     Connect,
     Disconnect,
     GetStationList,
+    /// Get the station list, but only if it has changed since it was last retrieved.
+    RefreshStationList,
     GetPlaylist(String),
     RateTrack(Track, Option<bool>),
     /// Add an artist or track as a seed for the given station (music_token from search).
@@ -34,6 +54,14 @@ pub(crate) enum PandoraCommand {
     ListSeeds(String),
     /// List rated tracks (loved/thumbs up, banned/thumbs down) for a station.
     ListRatedTracks(String),
+    /// Get a station's attributes, seeds and rating totals.
+    GetStationDetails(String),
+    /// Delete a thumbs-up or thumbs-down by its feedback_id (from rated tracks).
+    DeleteFeedback {
+        station_id: String,
+        feedback_id: String,
+        music_token: String,
+    },
     /// Create a station from a track token (e.g. from playlist); as_artist = use artist as seed.
     CreateStationFromTrack {
         track_token: String,
@@ -45,6 +73,11 @@ pub(crate) enum PandoraCommand {
     },
     /// Delete a station by its station token.
     DeleteStation(String),
+    /// Rename a station.
+    RenameStation {
+        station_id: String,
+        station_name: String,
+    },
     /// Add the current artist as a station seed (search by name, then add first match).
     AddArtistSeed {
         station_id: String,
@@ -52,9 +85,110 @@ pub(crate) enum PandoraCommand {
     },
     /// Remove a seed by its seed_id (from station seeds).
     RemoveSeed(String),
+    /// Search for artists, songs and genre stations matching free text.
+    Search(String),
+    /// Get the genre station categories (from the on-disk cache if still current).
+    GetGenreStations,
+    /// Get the Music Genome traits explaining why a track was chosen.
+    ExplainTrack(String),
+    /// Set which stations the QuickMix (Shuffle) station plays from.
+    SetQuickMix(Vec<String>),
+    /// Bookmark a track's song, or its artist if as_artist.
+    AddBookmark {
+        track_token: String,
+        as_artist: bool,
+    },
+    /// List bookmarked artists and songs.
+    GetBookmarks,
+    /// Shelve a track's song for a month ("tired of this song").
+    SleepSong(String),
+    /// Share a station with the given email addresses.
+    ShareStation {
+        station_id: String,
+        emails: Vec<String>,
+    },
+    /// Get the account's monthly listening hours and cap.
+    GetUsageInfo,
+    /// Add a station shared by another user (music token from `shared_station_token`),
+    /// and transform it so that it can be edited.
+    AcceptSharedStation(String),
     Quit,
 }
 
+impl PandoraCommand {
+    /// Short name of the command, for logging.
+    fn name(&self) -> &'static str {
+        match self {
+            PandoraCommand::Connect => "Connect",
+            PandoraCommand::Disconnect => "Disconnect",
+            PandoraCommand::GetStationList => "GetStationList",
+            PandoraCommand::RefreshStationList => "RefreshStationList",
+            PandoraCommand::GetPlaylist(_) => "GetPlaylist",
+            PandoraCommand::RateTrack(_, _) => "RateTrack",
+            PandoraCommand::AddSeed { .. } => "AddSeed",
+            PandoraCommand::ListSeeds(_) => "ListSeeds",
+            PandoraCommand::ListRatedTracks(_) => "ListRatedTracks",
+            PandoraCommand::GetStationDetails(_) => "GetStationDetails",
+            PandoraCommand::DeleteFeedback { .. } => "DeleteFeedback",
+            PandoraCommand::CreateStationFromTrack { .. } => "CreateStationFromTrack",
+            PandoraCommand::CreateStationFromMusic { .. } => "CreateStationFromMusic",
+            PandoraCommand::DeleteStation(_) => "DeleteStation",
+            PandoraCommand::RenameStation { .. } => "RenameStation",
+            PandoraCommand::AddArtistSeed { .. } => "AddArtistSeed",
+            PandoraCommand::RemoveSeed(_) => "RemoveSeed",
+            PandoraCommand::Search(_) => "Search",
+            PandoraCommand::GetGenreStations => "GetGenreStations",
+            PandoraCommand::ExplainTrack(_) => "ExplainTrack",
+            PandoraCommand::SetQuickMix(_) => "SetQuickMix",
+            PandoraCommand::AddBookmark { .. } => "AddBookmark",
+            PandoraCommand::SleepSong(_) => "SleepSong",
+            PandoraCommand::GetBookmarks => "GetBookmarks",
+            PandoraCommand::ShareStation { .. } => "ShareStation",
+            PandoraCommand::GetUsageInfo => "GetUsageInfo",
+            PandoraCommand::AcceptSharedStation(_) => "AcceptSharedStation",
+            PandoraCommand::Quit => "Quit",
+        }
+    }
+
+    /// The station the command acts on, if any.
+    fn station_id(&self) -> Option<&str> {
+        match self {
+            PandoraCommand::GetPlaylist(station_id)
+            | PandoraCommand::AddSeed { station_id, .. }
+            | PandoraCommand::ListSeeds(station_id)
+            | PandoraCommand::ListRatedTracks(station_id)
+            | PandoraCommand::GetStationDetails(station_id)
+            | PandoraCommand::DeleteFeedback { station_id, .. }
+            | PandoraCommand::DeleteStation(station_id)
+            | PandoraCommand::RenameStation { station_id, .. }
+            | PandoraCommand::AddArtistSeed { station_id, .. }
+            | PandoraCommand::ShareStation { station_id, .. } => Some(station_id),
+            _ => None,
+        }
+    }
+
+    /// The station whose commands have to run in the order they were sent, if
+    /// any.  Playlist fetches change nothing on the station, and can be slow,
+    /// so they're left out to keep them from holding up e.g. a rating.
+    fn ordering_key(&self) -> Option<&str> {
+        match self {
+            PandoraCommand::GetPlaylist(_) => None,
+            PandoraCommand::RateTrack(track, _) => Some(&track.station_id),
+            command => command.station_id(),
+        }
+    }
+}
+
+/// A station in the account's station list.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Station {
+    pub station_id: String,
+    pub station_name: String,
+    pub art_url: Option<String>,
+    pub genres: Vec<String>,
+    pub date_created: Option<chrono::NaiveDate>,
+}
+
 /// Seed info for one artist or song seed on a station.
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
@@ -74,17 +208,26 @@ pub(crate) struct SongSeedInfo {
     pub artist_name: String,
 }
 
+/// Seed info for one genre seed on a station.
+#[derive(Debug, Clone)]
+#[allow(dead_code)]
+pub(crate) struct GenreSeedInfo {
+    pub seed_id: String,
+    pub music_token: String,
+    pub genre_name: String,
+}
+
 /// All seeds for a station.
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub(crate) struct StationSeedsData {
     pub artist_seeds: Vec<ArtistSeedInfo>,
     pub song_seeds: Vec<SongSeedInfo>,
+    pub genre_seeds: Vec<GenreSeedInfo>,
 }
 
 /// One rated track (loved or banned) on a station.
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
 pub(crate) struct RatedTrackInfo {
     pub feedback_id: String,
     pub music_token: String,
@@ -95,412 +238,1069 @@ pub(crate) struct RatedTrackInfo {
 
 /// All rated tracks (thumbs up / thumbs down) for a station.
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
 pub(crate) struct StationRatedTracksData {
     pub thumbs_up: Vec<RatedTrackInfo>,
     pub thumbs_down: Vec<RatedTrackInfo>,
 }
 
+/// Everything shown in a station's info panel.  Getting the seeds and rating
+/// totals takes a request per station, so these are only loaded on demand.
+#[derive(Debug, Clone)]
+pub(crate) struct StationDetails {
+    pub station: Station,
+    pub seeds: StationSeedsData,
+    pub thumbs_up: u32,
+    pub thumbs_down: u32,
+}
+
+/// The account's QuickMix (Shuffle) station, and the stations it plays from.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct QuickMix {
+    pub station_id: Option<String>,
+    pub station_ids: Vec<String>,
+}
+
+/// One match from a music search or bookmark, carrying the music token used
+/// to create a station or add a seed.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum MusicMatch {
+    Artist {
+        artist_name: String,
+        music_token: String,
+    },
+    Song {
+        song_name: String,
+        artist_name: String,
+        music_token: String,
+    },
+    Genre {
+        station_name: String,
+        music_token: String,
+    },
+}
+
+impl MusicMatch {
+    pub(crate) fn music_token(&self) -> &str {
+        match self {
+            MusicMatch::Artist { music_token, .. } => music_token,
+            MusicMatch::Song { music_token, .. } => music_token,
+            MusicMatch::Genre { music_token, .. } => music_token,
+        }
+    }
+}
+
+impl std::fmt::Display for MusicMatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MusicMatch::Artist { artist_name, .. } => write!(f, "Artist: {artist_name}"),
+            MusicMatch::Song {
+                song_name,
+                artist_name,
+                ..
+            } => write!(f, "Song: {song_name} by {artist_name}"),
+            MusicMatch::Genre { station_name, .. } => write!(f, "Genre: {station_name}"),
+        }
+    }
+}
+
+/// Results of a music search, in the order artists, songs, genre stations.
+#[derive(Debug, Clone)]
+pub(crate) struct SearchResults {
+    /// The text that was searched for.
+    pub query: String,
+    pub matches: Vec<MusicMatch>,
+}
+
+/// Account listening time for the month, and the limit on it.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct UsageInfo {
+    pub listening_hours: u32,
+    /// Zero if the account has no monthly cap.
+    pub cap_hours: u32,
+    pub capped: bool,
+}
+
+impl std::fmt::Display for UsageInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.cap_hours == 0 {
+            write!(f, "{}h listened this month", self.listening_hours)
+        } else {
+            write!(
+                f,
+                "{}h of {}h listened this month",
+                self.listening_hours, self.cap_hours
+            )?;
+            if self.capped {
+                write!(f, " (limit reached)")?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Extract the music token for a shared station from what the user pasted,
+/// either a share link (e.g. `https://www.pandora.com/?sc=sh123456789` or
+/// `https://www.pandora.com/station/123456789`) or the token itself. Bare
+/// station ids are given the `sh` prefix that marks a shared station token.
+pub(crate) fn shared_station_token(text: &str) -> Option<String> {
+    let text = text.trim();
+    let token = if let Some((_, query)) = text.split_once('?') {
+        query
+            .split('&')
+            .find_map(|param| param.strip_prefix("sc="))
+            .unwrap_or_default()
+    } else {
+        text.trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or_default()
+    };
+    if token.is_empty() || !token.chars().all(|c| c.is_ascii_alphanumeric()) {
+        None
+    } else if token.chars().all(|c| c.is_ascii_digit()) {
+        Some(format!("sh{token}"))
+    } else {
+        Some(token.to_string())
+    }
+}
+
 /// Results the Pandora task sends back to the model.
 #[derive(Debug)]
 #[allow(dead_code)]
 pub(crate) enum PandoraResult {
     Connected,
     AuthFailed(String),
+    /// The settings needed to reach Pandora are unusable (description of the
+    /// problem), so there's no point trying again until they change.
+    InvalidConfig(String),
     Disconnected,
-    StationList(HashMap<String, String>),
+    /// The command was dropped before it finished, by a disconnect or a failed
+    /// login, so there's no other result coming for it.
+    Cancelled,
+    StationList(HashMap<String, Station>, QuickMix),
+    /// The station list hasn't changed since it was last retrieved.
+    StationListCurrent,
     Playlist(Vec<Track>),
     Rated(u32),
     Seeds(String, StationSeedsData),
-    RatedTracks(StationRatedTracksData),
+    RatedTracks(String, StationRatedTracksData),
+    StationDetails(StationDetails),
+    FeedbackDeleted {
+        station_id: String,
+        feedback_id: String,
+        music_token: String,
+    },
     StationCreated {
         station_token: String,
         station_name: String,
     },
-    StationDeleted,
+    StationDeleted(String),
+    StationRenamed {
+        station_id: String,
+        station_name: String,
+    },
     SeedAdded {
         seed_id: String,
         artist_name: String,
     },
     SeedRemoved,
-    Error(String),
+    SearchResults(SearchResults),
+    GenreStations(Vec<GenreCategory>),
+    QuickMixSet(Vec<String>),
+    BookmarkAdded,
+    SongSlept(String),
+    StationShared(String),
+    UsageInfo(UsageInfo),
+    Bookmarks(Vec<MusicMatch>),
+    TrackExplanation {
+        track_token: String,
+        traits: Vec<String>,
+    },
+    /// A command failed, classified by what went wrong.
+    Error(Error),
+    /// A command failed in a way that should clear up by itself, and will be
+    /// tried again after the delay.
+    Retrying {
+        what: String,
+        delay: Duration,
+    },
     QuitAck,
 }
 
-/// Runs the Pandora API task. Receives commands, performs session/network work, sends results.
-pub(crate) async fn run_pandora_task(
+/// Correlates a `PandoraResult` with the `PandoraCommand` that produced it.
+pub(crate) type CommandId = u64;
+
+/// A command for the Pandora task, with the id its result will carry.
+#[derive(Debug, Clone)]
+pub(crate) struct TaggedCommand {
+    pub id: CommandId,
+    pub command: PandoraCommand,
+}
+
+/// A result from the Pandora task, with the id of the command that produced
+/// it (`None` for the refreshes the task runs on its own), and the station
+/// that command was for, if any.
+#[derive(Debug)]
+pub(crate) struct TaggedResult {
+    pub id: Option<CommandId>,
+    pub station_id: Option<String>,
+    pub result: PandoraResult,
+}
+
+/// A command that ran alongside others, and how it went.
+struct Finished {
+    id: Option<CommandId>,
+    command: PandoraCommand,
+    /// Login generation of the session the command ran with.
+    generation: u64,
+    /// Whether this was already the retry after logging in again.
+    retried: bool,
+    /// How many times the command was tried again after failing.
+    retries: u32,
+    result: Result<PandoraResult>,
+}
+
+/// A command waiting out a retry delay, or a cooldown on its class, before it
+/// runs.  It only takes the session once it does, so that it runs with the
+/// latest tokens.
+struct Deferred {
+    id: Option<CommandId>,
+    command: PandoraCommand,
+    retried: bool,
+    retries: u32,
+    at: Instant,
+    /// Why the command failed last time, if it was tried already.
+    error: Option<Error>,
+}
+
+/// A login that failed in a way that should clear up by itself, to be tried
+/// again at the given time.
+struct LoginRetry {
+    id: Option<CommandId>,
+    retries: u32,
+    at: Instant,
+}
+
+/// State of the Pandora task.  Logging in and out happens one at a time in
+/// the task itself, while every other command runs concurrently on its own
+/// clone of the session, so that a slow request doesn't hold up the rest.
+struct PandoraTask {
     config: SharedConfig,
-    mut command_rx: mpsc::Receiver<PandoraCommand>,
-    result_tx: mpsc::Sender<PandoraResult>,
-) {
-    let mut session: Option<PandoraSession> = None;
+    session: Option<PandoraSession>,
+    /// Bumped on every login, to tell which commands ran with tokens that
+    /// have since been replaced.
+    generation: u64,
+    in_flight: JoinSet<Finished>,
+    /// The commands in flight that the model is waiting on, so that it can be
+    /// told if they're dropped.
+    running: HashMap<CommandId, PandoraCommand>,
+    deferred: Vec<Deferred>,
+    /// Stations with a command in flight, and the commands waiting behind it.
+    station_queues: HashMap<String, VecDeque<(Option<CommandId>, PandoraCommand)>>,
+    /// Stations whose queued commands wait for a login to finish, since the
+    /// command ahead of them was dropped when it started.
+    held_stations: Vec<String>,
+    /// Classes of command Pandora has throttled, and when to resume sending them.
+    cooldowns: HashMap<CommandClass, Instant>,
+    login_retry: Option<LoginRetry>,
+    result_tx: mpsc::Sender<TaggedResult>,
+}
+
+impl PandoraTask {
+    fn new(config: SharedConfig, result_tx: mpsc::Sender<TaggedResult>) -> Self {
+        Self {
+            config,
+            session: None,
+            generation: 0,
+            in_flight: JoinSet::new(),
+            running: HashMap::new(),
+            deferred: Vec::new(),
+            station_queues: HashMap::new(),
+            held_stations: Vec::new(),
+            cooldowns: HashMap::new(),
+            login_retry: None,
+            result_tx,
+        }
+    }
+
+    fn connected(&self) -> bool {
+        self.session.as_ref().is_some_and(|s| s.connected())
+    }
+
+    async fn send(&self, id: Option<CommandId>, command: &PandoraCommand, result: PandoraResult) {
+        let _ = self
+            .result_tx
+            .send(TaggedResult {
+                id,
+                station_id: command.station_id().map(String::from),
+                result,
+            })
+            .await;
+    }
+
+    fn cooldown_remaining(&self, class: CommandClass) -> Duration {
+        self.cooldowns
+            .get(&class)
+            .map(|until| until.saturating_duration_since(Instant::now()))
+            .unwrap_or_default()
+    }
+
+    /// The delay before the given retry of a command, holding off for any
+    /// cooldown on its class, or `None` once the retries are used up.
+    fn retry_delay(&self, class: CommandClass, retry: u32) -> Option<Duration> {
+        class
+            .retry_delay(retry)
+            .map(|delay| delay.max(self.cooldown_remaining(class)))
+    }
+
+    /// Hold off on a class of command for as long as its last retry waited,
+    /// once its retries are used up, so that commands sent after it don't
+    /// start the retries over right away.
+    fn exhausted(&mut self, class: CommandClass, retries: u32) {
+        if let Some(delay) = class.retry_delay(retries) {
+            let until = Instant::now() + delay;
+            let cooldown = self.cooldowns.entry(class).or_insert(until);
+            *cooldown = (*cooldown).max(until);
+        }
+    }
 
-    while let Some(cmd) = command_rx.recv().await {
-        match cmd {
+    /// Start a cooldown on a class of command, if Pandora throttled it.
+    fn throttled(&mut self, class: CommandClass, err: &Error) {
+        if matches!(err, Error::PandoraRateLimited(_)) {
+            let cooldown = jitter(THROTTLE_COOLDOWN);
+            warn!(
+                "Pandora task: throttled, holding off on {class} requests for {}s",
+                cooldown.as_secs()
+            );
+            self.cooldowns.insert(class, Instant::now() + cooldown);
+        }
+    }
+
+    /// Handle a command from the model, returning whether the task should quit.
+    async fn handle(&mut self, id: Option<CommandId>, command: PandoraCommand) -> bool {
+        match command {
             PandoraCommand::Connect => {
-                if session.as_ref().is_some_and(|s| s.connected()) {
-                    debug!("Pandora task: already connected, ignoring Connect");
-                    continue;
-                }
-                let mut new_session = PandoraSession::new(config.clone());
-                match do_connect(&mut new_session).await {
-                    Ok(()) => {
-                        session = Some(new_session);
-                        trace!("Pandora task: connected");
-                        let _ = result_tx.send(PandoraResult::Connected).await;
-                    }
-                    Err(e) => {
-                        let message = if e
-                            .downcast_ref::<Error>()
-                            .is_some_and(|e| e.missing_auth_token())
-                        {
-                            String::from("Required authentication token is missing.")
-                        } else if let Some(e) = e.downcast_ref::<pandora_api::errors::Error>() {
-                            format!("Pandora authentication failure: {e:#}")
-                        } else {
-                            format!("Unknown error while logging in: {e:#}")
-                        };
-                        error!("Pandora task: connect failed: {message}");
-                        session = None;
-                        let _ = result_tx.send(PandoraResult::AuthFailed(message)).await;
-                    }
-                }
+                self.login_retry = None;
+                self.connect(id, 0).await;
             }
             PandoraCommand::Disconnect => {
-                if let Some(mut s) = session.take() {
+                self.login_retry = None;
+                self.abort_all().await;
+                if let Some(mut s) = self.session.take() {
                     trace!("Pandora task: disconnecting...");
                     s.partner_logout().await;
                     trace!("Pandora task: disconnected");
                 }
-                let _ = result_tx.send(PandoraResult::Disconnected).await;
+                self.send(id, &command, PandoraResult::Disconnected).await;
             }
-            PandoraCommand::GetStationList => {
-                let sess = match session.as_mut() {
-                    Some(s) if s.connected() => s,
-                    _ => {
-                        warn!("Pandora task: GetStationList while not connected");
-                        let _ = result_tx
-                            .send(PandoraResult::Error("Not connected".into()))
-                            .await;
-                        continue;
-                    }
-                };
-                match sess.get_station_list().await {
-                    Ok(resp) => {
-                        let list = resp
-                            .stations
-                            .into_iter()
-                            .map(|s| (s.station_id, s.station_name))
-                            .collect::<HashMap<_, _>>();
-                        let _ = result_tx.send(PandoraResult::StationList(list)).await;
-                    }
-                    Err(e) => {
-                        error!("Pandora task: get_station_list failed: {e:#}");
-                        let _ = result_tx.send(PandoraResult::Error(format!("{e:#}"))).await;
-                    }
+            PandoraCommand::Quit => {
+                self.login_retry = None;
+                self.abort_all().await;
+                if let Some(mut s) = self.session.take() {
+                    s.partner_logout().await;
                 }
+                self.send(id, &command, PandoraResult::QuitAck).await;
+                return true;
             }
-            PandoraCommand::GetPlaylist(station_id) => {
-                let sess = match session.as_mut() {
-                    Some(s) if s.connected() => s,
-                    _ => {
-                        warn!("Pandora task: GetPlaylist while not connected");
-                        let _ = result_tx
-                            .send(PandoraResult::Error("Not connected".into()))
-                            .await;
-                        continue;
-                    }
-                };
-                match sess.get_playlist(&station_id).await {
-                    Ok(entries) => {
-                        let playlist: Result<Vec<Track>> = entries
-                            .into_iter()
-                            .flat_map(|pe| pe.get_track().map(Track::try_from).into_iter())
-                            .collect();
-                        match playlist {
-                            Ok(tracks) => {
-                                debug!("Pandora task: got {} tracks", tracks.len());
-                                let _ = result_tx.send(PandoraResult::Playlist(tracks)).await;
-                            }
-                            Err(e) => {
-                                error!("Pandora task: playlist track conversion failed: {e:#}");
-                                let _ =
-                                    result_tx.send(PandoraResult::Error(format!("{e:#}"))).await;
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        error!("Pandora task: get_playlist failed: {e:#}");
-                        let _ = result_tx.send(PandoraResult::Error(format!("{e:#}"))).await;
-                    }
-                }
+            command => self.submit(id, command),
+        }
+        false
+    }
+
+    async fn connect(&mut self, id: Option<CommandId>, retries: u32) {
+        let command = &PandoraCommand::Connect;
+        if self.connected() {
+            debug!("Pandora task: already connected, ignoring Connect");
+            return;
+        }
+        // Commands still running finish with the session they have, and log
+        // in again if its tokens were replaced, but there's no sense in
+        // keeping the ones waiting to try again without a session
+        self.abort_deferred().await;
+        let cooldown = self.cooldown_remaining(CommandClass::Login);
+        if !cooldown.is_zero() {
+            self.login_retry = Some(LoginRetry {
+                id,
+                retries,
+                at: Instant::now() + cooldown,
+            });
+            let what = CommandClass::Login.to_string();
+            self.send(
+                id,
+                command,
+                PandoraResult::Retrying {
+                    what,
+                    delay: cooldown,
+                },
+            )
+            .await;
+            return;
+        }
+        let mut new_session = match PandoraSession::new(self.config.clone()) {
+            Ok(s) => s,
+            Err(e) => {
+                let message = format!("Invalid network settings: {e:#}");
+                error!("Pandora task: {message}");
+                self.cancel_held().await;
+                self.send(id, command, PandoraResult::InvalidConfig(message))
+                    .await;
+                return;
             }
-            PandoraCommand::RateTrack(track, rating) => {
-                let sess = match session.as_mut() {
-                    Some(s) if s.connected() => s,
-                    _ => {
-                        warn!("Pandora task: RateTrack while not connected");
-                        let _ = result_tx
-                            .send(PandoraResult::Error("Not connected".into()))
-                            .await;
-                        continue;
+        };
+        match do_connect(&mut new_session).await {
+            Ok(()) => {
+                self.session = Some(new_session);
+                self.generation += 1;
+                trace!("Pandora task: connected");
+                self.send(id, command, PandoraResult::Connected).await;
+                self.resume_held();
+            }
+            Err(e) => {
+                self.session = None;
+                let err = api::classify_error(&e, None);
+                if err.pandora_transient() {
+                    // Nothing wrong with the credentials, Pandora just couldn't
+                    // be reached, so try again later
+                    error!("Pandora task: connect failed: {err}");
+                    self.throttled(CommandClass::Login, &err);
+                    match self.retry_delay(CommandClass::Login, retries + 1) {
+                        Some(delay) => {
+                            self.login_retry = Some(LoginRetry {
+                                id,
+                                retries: retries + 1,
+                                at: Instant::now() + delay,
+                            });
+                            let what = CommandClass::Login.to_string();
+                            self.send(id, command, PandoraResult::Retrying { what, delay })
+                                .await;
+                        }
+                        None => {
+                            self.exhausted(CommandClass::Login, retries);
+                            self.cancel_held().await;
+                            self.send(id, command, PandoraResult::Error(err)).await
+                        }
                     }
-                };
-                let new_rating_value = if rating.unwrap_or(false) { 1 } else { 0 };
-                let res = if let Some(r) = rating {
-                    sess.add_feedback(&track, r).await.map(|_| ())
                 } else {
-                    sess.delete_feedback_for_track(&track).await
-                };
-                match res {
-                    Ok(()) => {
-                        trace!("Pandora task: rated track");
-                        let _ = result_tx.send(PandoraResult::Rated(new_rating_value)).await;
-                    }
-                    Err(e) => {
-                        error!("Pandora task: rate failed: {e:#}");
-                        let _ = result_tx.send(PandoraResult::Error(format!("{e:#}"))).await;
-                    }
+                    let message = auth_failure_message(&e);
+                    error!("Pandora task: connect failed: {message}");
+                    self.cancel_held().await;
+                    self.send(id, command, PandoraResult::AuthFailed(message))
+                        .await;
                 }
             }
-            PandoraCommand::AddSeed {
-                station_id,
-                music_token,
-            } => {
-                let sess = match session.as_mut() {
-                    Some(s) if s.connected() => s,
-                    _ => {
-                        warn!("Pandora task: AddSeed while not connected");
-                        let _ = result_tx
-                            .send(PandoraResult::Error("Not connected".into()))
-                            .await;
-                        continue;
-                    }
-                };
-                match sess.add_music(&station_id, &music_token).await {
-                    Ok(resp) => {
-                        trace!("Pandora task: added seed");
-                        let _ = result_tx
-                            .send(PandoraResult::SeedAdded {
-                                seed_id: resp.seed_id,
-                                artist_name: resp.artist_name,
-                            })
-                            .await;
-                    }
-                    Err(e) => {
-                        error!("Pandora task: add_music failed: {e:#}");
-                        let _ = result_tx.send(PandoraResult::Error(format!("{e:#}"))).await;
-                    }
+        }
+    }
+
+    /// Start a command, unless its station already has one running, in which
+    /// case it waits its turn.
+    fn submit(&mut self, id: Option<CommandId>, command: PandoraCommand) {
+        if let Some(station_id) = command.ordering_key() {
+            match self.station_queues.get_mut(station_id) {
+                Some(queue) => {
+                    trace!(
+                        "Pandora task: {} waiting on station {station_id}",
+                        command.name()
+                    );
+                    queue.push_back((id, command));
+                    return;
                 }
-            }
-            PandoraCommand::ListSeeds(station_id) => {
-                let sess = match session.as_mut() {
-                    Some(s) if s.connected() => s,
-                    _ => {
-                        warn!("Pandora task: ListSeeds while not connected");
-                        let _ = result_tx
-                            .send(PandoraResult::Error("Not connected".into()))
-                            .await;
-                        continue;
-                    }
-                };
-                let station_id = station_id.clone();
-                match sess.get_station_seeds(&station_id).await {
-                    Ok(seeds) => {
-                        let _ = result_tx
-                            .send(PandoraResult::Seeds(station_id, seeds))
-                            .await;
-                    }
-                    Err(e) => {
-                        error!("Pandora task: get_station_seeds failed: {e:#}");
-                        let _ = result_tx.send(PandoraResult::Error(format!("{e:#}"))).await;
-                    }
+                None => {
+                    self.station_queues
+                        .insert(station_id.to_string(), VecDeque::new());
                 }
             }
-            PandoraCommand::ListRatedTracks(station_id) => {
-                let sess = match session.as_mut() {
-                    Some(s) if s.connected() => s,
-                    _ => {
-                        warn!("Pandora task: ListRatedTracks while not connected");
-                        let _ = result_tx
-                            .send(PandoraResult::Error("Not connected".into()))
-                            .await;
-                        continue;
-                    }
+        }
+        self.start(id, command);
+    }
+
+    /// Run a command now, or once any cooldown on its class is over.
+    fn start(&mut self, id: Option<CommandId>, command: PandoraCommand) {
+        let cooldown = self.cooldown_remaining(CommandClass::of(&command));
+        if cooldown.is_zero() {
+            self.spawn(id, command, false, 0);
+        } else {
+            self.defer(id, command, false, 0, cooldown, None);
+        }
+    }
+
+    /// Log in again after an earlier attempt failed.
+    async fn retry_login(&mut self) {
+        if let Some(LoginRetry { id, retries, .. }) = self.login_retry.take() {
+            trace!("Pandora task: retrying login");
+            self.connect(id, retries).await;
+        }
+    }
+
+    /// Run a command after a delay.
+    fn defer(
+        &mut self,
+        id: Option<CommandId>,
+        command: PandoraCommand,
+        retried: bool,
+        retries: u32,
+        delay: Duration,
+        error: Option<Error>,
+    ) {
+        trace!(
+            "Pandora task: {} deferred for {}s",
+            command.name(),
+            delay.as_secs()
+        );
+        self.deferred.push(Deferred {
+            id,
+            command,
+            retried,
+            retries,
+            at: Instant::now() + delay,
+            error,
+        });
+    }
+
+    /// When the next deferred command is due to run.
+    fn next_deferred_at(&self) -> Option<Instant> {
+        self.deferred.iter().map(|d| d.at).min()
+    }
+
+    /// Run the deferred commands that are due.
+    fn start_deferred(&mut self) {
+        let now = Instant::now();
+        let (due, waiting): (Vec<Deferred>, Vec<Deferred>) = std::mem::take(&mut self.deferred)
+            .into_iter()
+            .partition(|d| d.at <= now);
+        self.deferred = waiting;
+        for d in due {
+            self.spawn(d.id, d.command, d.retried, d.retries);
+        }
+    }
+
+    /// Drop the deferred commands, failing them with the error they last
+    /// failed with.  The commands queued behind them wait for the login that
+    /// is starting.
+    async fn abort_deferred(&mut self) {
+        for d in std::mem::take(&mut self.deferred) {
+            debug!("Pandora task: dropping deferred {}", d.command.name());
+            if d.id.is_some() {
+                let result = match d.error {
+                    Some(err) => PandoraResult::Error(err),
+                    None => PandoraResult::Cancelled,
                 };
-                match sess.get_station_rated_tracks(&station_id).await {
-                    Ok(rated) => {
-                        let _ = result_tx.send(PandoraResult::RatedTracks(rated)).await;
-                    }
-                    Err(e) => {
-                        error!("Pandora task: get_station_rated_tracks failed: {e:#}");
-                        let _ = result_tx.send(PandoraResult::Error(format!("{e:#}"))).await;
-                    }
-                }
+                self.send(d.id, &d.command, result).await;
             }
-            PandoraCommand::CreateStationFromTrack {
-                track_token,
-                as_artist,
-            } => {
-                let sess = match session.as_mut() {
-                    Some(s) if s.connected() => s,
-                    _ => {
-                        warn!("Pandora task: CreateStationFromTrack while not connected");
-                        let _ = result_tx
-                            .send(PandoraResult::Error("Not connected".into()))
-                            .await;
-                        continue;
-                    }
-                };
-                let create = if as_artist {
-                    sess.create_station_from_track_artist(&track_token).await
-                } else {
-                    sess.create_station_from_track_song(&track_token).await
-                };
-                match create {
-                    Ok(resp) => {
-                        trace!("Pandora task: created station from track");
-                        let _ = result_tx
-                            .send(PandoraResult::StationCreated {
-                                station_token: resp.station_token.clone(),
-                                station_name: String::new(),
-                            })
-                            .await;
-                    }
-                    Err(e) => {
-                        error!("Pandora task: create_station failed: {e:#}");
-                        let _ = result_tx.send(PandoraResult::Error(format!("{e:#}"))).await;
-                    }
-                }
+            if let Some(station_id) = d.command.ordering_key() {
+                self.held_stations.push(station_id.to_string());
             }
-            PandoraCommand::CreateStationFromMusic { music_token } => {
-                let sess = match session.as_mut() {
-                    Some(s) if s.connected() => s,
-                    _ => {
-                        warn!("Pandora task: CreateStationFromMusic while not connected");
-                        let _ = result_tx
-                            .send(PandoraResult::Error("Not connected".into()))
-                            .await;
-                        continue;
-                    }
-                };
-                match sess.create_station_from_music_token(&music_token).await {
-                    Ok(resp) => {
-                        trace!("Pandora task: created station from music");
-                        let _ = result_tx
-                            .send(PandoraResult::StationCreated {
-                                station_token: resp.station_token.clone(),
-                                station_name: String::new(),
-                            })
-                            .await;
-                    }
-                    Err(e) => {
-                        error!("Pandora task: create_station failed: {e:#}");
-                        let _ = result_tx.send(PandoraResult::Error(format!("{e:#}"))).await;
-                    }
-                }
+        }
+    }
+
+    /// Start the commands held for a login, now that it's done.
+    fn resume_held(&mut self) {
+        for station_id in std::mem::take(&mut self.held_stations) {
+            self.start_next_on(&station_id);
+        }
+    }
+
+    /// Drop the commands held for a login that failed.
+    async fn cancel_held(&mut self) {
+        for station_id in std::mem::take(&mut self.held_stations) {
+            if let Some(queue) = self.station_queues.remove(&station_id) {
+                self.cancel(queue).await;
             }
-            PandoraCommand::DeleteStation(station_id) => {
-                let sess = match session.as_mut() {
-                    Some(s) if s.connected() => s,
-                    _ => {
-                        warn!("Pandora task: DeleteStation while not connected");
-                        let _ = result_tx
-                            .send(PandoraResult::Error("Not connected".into()))
-                            .await;
-                        continue;
-                    }
-                };
-                match sess.delete_station(&station_id).await {
-                    Ok(()) => {
-                        trace!("Pandora task: deleted station");
-                        let _ = result_tx.send(PandoraResult::StationDeleted).await;
-                    }
-                    Err(e) => {
-                        error!("Pandora task: delete_station failed: {e:#}");
-                        let _ = result_tx.send(PandoraResult::Error(format!("{e:#}"))).await;
-                    }
+        }
+    }
+
+    /// Tell the model about commands it's waiting on that won't run after all.
+    async fn cancel(
+        &self,
+        commands: impl IntoIterator<Item = (Option<CommandId>, PandoraCommand)>,
+    ) {
+        for (id, command) in commands {
+            if id.is_some() {
+                debug!("Pandora task: cancelling {}", command.name());
+                self.send(id, &command, PandoraResult::Cancelled).await;
+            }
+        }
+    }
+
+    /// Run a command alongside any others, with the session as it is now.
+    fn spawn(
+        &mut self,
+        id: Option<CommandId>,
+        command: PandoraCommand,
+        retried: bool,
+        retries: u32,
+    ) {
+        let session = self.session.clone().filter(|s| s.connected());
+        let generation = self.generation;
+        if let Some(id) = id {
+            self.running.insert(id, command.clone());
+        }
+        self.in_flight.spawn(async move {
+            let result = match session {
+                Some(mut sess) => execute_command(&mut sess, &command).await,
+                None => {
+                    warn!("Pandora task: {} while not connected", command.name());
+                    Ok(PandoraResult::Error(Error::invalid_operation_for_state(
+                        command.name(),
+                        "Disconnected",
+                    )))
                 }
+            };
+            Finished {
+                id,
+                command,
+                generation,
+                retried,
+                retries,
+                result,
             }
-            PandoraCommand::AddArtistSeed {
-                station_id,
-                artist_name,
-            } => {
-                let sess = match session.as_mut() {
-                    Some(s) if s.connected() => s,
-                    _ => {
-                        warn!("Pandora task: AddArtistSeed while not connected");
-                        let _ = result_tx
-                            .send(PandoraResult::Error("Not connected".into()))
-                            .await;
-                        continue;
-                    }
-                };
-                match sess.search(&artist_name).await {
-                    Ok(resp) => {
-                        if let Some(artist) = resp.artists.first() {
-                            match sess.add_music(&station_id, &artist.music_token).await {
-                                Ok(resp) => {
-                                    trace!("Pandora task: added artist seed");
-                                    let _ = result_tx
-                                        .send(PandoraResult::SeedAdded {
-                                            seed_id: resp.seed_id,
-                                            artist_name: resp.artist_name,
-                                        })
-                                        .await;
-                                }
-                                Err(e) => {
-                                    error!("Pandora task: add_music (artist) failed: {e:#}");
-                                    let _ = result_tx
-                                        .send(PandoraResult::Error(format!("{e:#}")))
-                                        .await;
-                                }
-                            }
-                        } else {
-                            warn!("Pandora task: no artist match for '{artist_name}'");
-                            let _ = result_tx
-                                .send(PandoraResult::Error(format!(
-                                    "No artist found for '{artist_name}'"
-                                )))
+        });
+    }
+
+    async fn finished(&mut self, joined: Result<Finished, JoinError>) {
+        let Finished {
+            id,
+            command,
+            generation,
+            retried,
+            retries,
+            result,
+        } = match joined {
+            Ok(finished) => finished,
+            Err(e) => {
+                error!("Pandora task: command did not complete: {e}");
+                return;
+            }
+        };
+        if let Some(id) = id {
+            self.running.remove(&id);
+        }
+        match result {
+            Err(e) if !retried && api::session_expired(&e) => {
+                // The auth tokens went stale underneath us (e.g. a long-running
+                // session), so log in from scratch and give the command one more
+                // try.  Other commands that were running with the same tokens
+                // will end up here too, but only need the retry.
+                if generation == self.generation {
+                    if let Some(sess) = self.session.as_mut() {
+                        warn!(
+                            "Pandora task: session expired during {}, logging in again",
+                            command.name()
+                        );
+                        sess.partner_logout().await;
+                        if let Err(e) = do_connect(sess).await {
+                            let message = auth_failure_message(&e);
+                            error!("Pandora task: re-login failed: {message}");
+                            self.session = None;
+                            self.send(id, &command, PandoraResult::AuthFailed(message))
                                 .await;
+                            self.start_next(&command);
+                            return;
                         }
-                    }
-                    Err(e) => {
-                        error!("Pandora task: search for artist failed: {e:#}");
-                        let _ = result_tx.send(PandoraResult::Error(format!("{e:#}"))).await;
+                        self.generation += 1;
                     }
                 }
+                trace!("Pandora task: retrying {}", command.name());
+                self.spawn(id, command, true, retries);
+                return;
+            }
+            Ok(res) => self.send(id, &command, res).await,
+            Err(e)
+                if matches!(
+                    command,
+                    PandoraCommand::GetUsageInfo | PandoraCommand::RefreshStationList
+                ) =>
+            {
+                // Background refreshes will be tried again later, so failures
+                // aren't worth disturbing whatever else the model is waiting on
+                warn!("Pandora task: {} failed: {e:#}", command.name());
             }
-            PandoraCommand::RemoveSeed(seed_id) => {
-                let sess = match session.as_mut() {
-                    Some(s) if s.connected() => s,
-                    _ => {
-                        warn!("Pandora task: RemoveSeed while not connected");
-                        let _ = result_tx
-                            .send(PandoraResult::Error("Not connected".into()))
+            Err(e) => {
+                let err = api::classify_error(&e, command.station_id());
+                let class = CommandClass::of(&command);
+                if err.pandora_transient() {
+                    self.throttled(class, &err);
+                    if let Some(delay) = self.retry_delay(class, retries + 1) {
+                        warn!(
+                            "Pandora task: {} failed: {err}, retrying in {}s",
+                            command.name(),
+                            delay.as_secs()
+                        );
+                        let what = class.to_string();
+                        self.send(id, &command, PandoraResult::Retrying { what, delay })
                             .await;
-                        continue;
-                    }
-                };
-                match sess.delete_music(&seed_id).await {
-                    Ok(()) => {
-                        trace!("Pandora task: removed seed");
-                        let _ = result_tx.send(PandoraResult::SeedRemoved).await;
-                    }
-                    Err(e) => {
-                        error!("Pandora task: delete_music failed: {e:#}");
-                        let _ = result_tx.send(PandoraResult::Error(format!("{e:#}"))).await;
+                        self.defer(id, command, retried, retries + 1, delay, Some(err));
+                        return;
                     }
+                    self.exhausted(class, retries);
                 }
+                error!("Pandora task: {} failed: {err}", command.name());
+                self.send(id, &command, PandoraResult::Error(err)).await;
             }
-            PandoraCommand::Quit => {
-                if let Some(mut s) = session.take() {
-                    s.partner_logout().await;
+        }
+        self.start_next(&command);
+    }
+
+    /// Start the next command waiting on the station a finished command was for.
+    fn start_next(&mut self, command: &PandoraCommand) {
+        if let Some(station_id) = command.ordering_key() {
+            self.start_next_on(station_id);
+        }
+    }
+
+    /// Start the next command waiting on a station, if there is one.
+    fn start_next_on(&mut self, station_id: &str) {
+        match self
+            .station_queues
+            .get_mut(station_id)
+            .and_then(VecDeque::pop_front)
+        {
+            Some((id, command)) => self.start(id, command),
+            None => {
+                self.station_queues.remove(station_id);
+            }
+        }
+    }
+
+    /// Drop every running and waiting command, letting the model know about
+    /// the ones it was waiting on.
+    async fn abort_all(&mut self) {
+        self.in_flight.shutdown().await;
+        let running: Vec<_> = self
+            .running
+            .drain()
+            .map(|(id, command)| (Some(id), command))
+            .collect();
+        self.cancel(running).await;
+        let deferred: Vec<_> = self.deferred.drain(..).map(|d| (d.id, d.command)).collect();
+        self.cancel(deferred).await;
+        let queued: Vec<_> = self
+            .station_queues
+            .drain()
+            .flat_map(|(_, queue)| queue)
+            .collect();
+        self.cancel(queued).await;
+        self.held_stations.clear();
+    }
+}
+
+/// Runs the Pandora API task. Receives commands, performs session/network work, sends results.
+pub(crate) async fn run_pandora_task(
+    config: SharedConfig,
+    mut command_rx: mpsc::Receiver<TaggedCommand>,
+    result_tx: mpsc::Sender<TaggedResult>,
+) {
+    let mut task = PandoraTask::new(config, result_tx);
+    let mut usage_refresh = tokio::time::interval(USAGE_REFRESH_INTERVAL);
+    usage_refresh.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    // The model asks for the station list when it connects, so there's no
+    // need to check it again right away
+    let mut station_list_refresh = tokio::time::interval_at(
+        tokio::time::Instant::now() + STATION_LIST_REFRESH_INTERVAL,
+        STATION_LIST_REFRESH_INTERVAL,
+    );
+    station_list_refresh.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        let connected = task.connected();
+        let login_retry_at = task.login_retry.as_ref().map(|r| r.at);
+        let deferred_at = task.next_deferred_at();
+        tokio::select! {
+            cmd = command_rx.recv() => match cmd {
+                Some(TaggedCommand { id, command }) => {
+                    if task.handle(Some(id), command).await {
+                        return;
+                    }
                 }
-                let _ = result_tx.send(PandoraResult::QuitAck).await;
-                return;
+                None => break,
+            },
+            Some(joined) = task.in_flight.join_next() => task.finished(joined).await,
+            _ = tokio::time::sleep_until(login_retry_at.unwrap_or_else(Instant::now)),
+                if login_retry_at.is_some() => task.retry_login().await,
+            _ = tokio::time::sleep_until(deferred_at.unwrap_or_else(Instant::now)),
+                if deferred_at.is_some() => task.start_deferred(),
+            // Keep the account usage info fresh without the model having to ask
+            _ = usage_refresh.tick(), if connected => task.submit(None, PandoraCommand::GetUsageInfo),
+            _ = station_list_refresh.tick(), if connected => {
+                task.submit(None, PandoraCommand::RefreshStationList)
+            }
+        }
+    }
+}
+
+/// Performs the session work for a single command that requires an active session.
+async fn execute_command(sess: &mut PandoraSession, cmd: &PandoraCommand) -> Result<PandoraResult> {
+    match cmd {
+        PandoraCommand::RefreshStationList if !sess.station_list_changed().await? => {
+            Ok(PandoraResult::StationListCurrent)
+        }
+        PandoraCommand::GetStationList | PandoraCommand::RefreshStationList => {
+            let resp = sess.get_station_list().await?;
+            let quickmix = api::quickmix(&resp.stations);
+            let list = resp
+                .stations
+                .into_iter()
+                .map(|s| {
+                    let station = api::station_info(s.station_id, s.station_name, &s.optional);
+                    (station.station_id.clone(), station)
+                })
+                .collect::<HashMap<_, _>>();
+            Ok(PandoraResult::StationList(list, quickmix))
+        }
+        PandoraCommand::GetPlaylist(station_id) => {
+            let entries = sess.get_playlist(station_id).await?;
+            let audio_quality = sess.audio_quality();
+            let tracks = entries
+                .into_iter()
+                .flat_map(|pe| pe.get_track().map(Track::try_from).into_iter())
+                .map(|track| {
+                    track.map(|mut t| {
+                        t.prefer_audio_quality(audio_quality);
+                        t
+                    })
+                })
+                .collect::<Result<Vec<Track>>>()
+                .context("Playlist track conversion failed")?;
+            debug!("Pandora task: got {} tracks", tracks.len());
+            Ok(PandoraResult::Playlist(tracks))
+        }
+        PandoraCommand::RateTrack(track, rating) => {
+            let new_rating_value = if rating.unwrap_or(false) { 1 } else { 0 };
+            if let Some(r) = rating {
+                sess.add_feedback(track, *r).await?;
+            } else {
+                sess.delete_feedback_for_track(track).await?;
+            }
+            trace!("Pandora task: rated track");
+            Ok(PandoraResult::Rated(new_rating_value))
+        }
+        PandoraCommand::AddSeed {
+            station_id,
+            music_token,
+        } => {
+            let resp = sess.add_music(station_id, music_token).await?;
+            trace!("Pandora task: added seed");
+            Ok(PandoraResult::SeedAdded {
+                seed_id: resp.seed_id,
+                artist_name: resp.artist_name,
+            })
+        }
+        PandoraCommand::ListSeeds(station_id) => {
+            let seeds = sess.get_station_seeds(station_id).await?;
+            Ok(PandoraResult::Seeds(station_id.clone(), seeds))
+        }
+        PandoraCommand::ListRatedTracks(station_id) => {
+            let rated = sess.get_station_rated_tracks(station_id).await?;
+            Ok(PandoraResult::RatedTracks(station_id.clone(), rated))
+        }
+        PandoraCommand::GetStationDetails(station_id) => {
+            let details = sess.get_station_details(station_id).await?;
+            Ok(PandoraResult::StationDetails(details))
+        }
+        PandoraCommand::DeleteFeedback {
+            station_id,
+            feedback_id,
+            music_token,
+        } => {
+            sess.delete_feedback(feedback_id).await?;
+            trace!("Pandora task: deleted feedback");
+            Ok(PandoraResult::FeedbackDeleted {
+                station_id: station_id.clone(),
+                feedback_id: feedback_id.clone(),
+                music_token: music_token.clone(),
+            })
+        }
+        PandoraCommand::CreateStationFromTrack {
+            track_token,
+            as_artist,
+        } => {
+            let resp = if *as_artist {
+                sess.create_station_from_track_artist(track_token).await?
+            } else {
+                sess.create_station_from_track_song(track_token).await?
+            };
+            trace!("Pandora task: created station from track");
+            let station_name = created_station_name(sess, &resp.station_token).await;
+            Ok(PandoraResult::StationCreated {
+                station_token: resp.station_token,
+                station_name,
+            })
+        }
+        PandoraCommand::CreateStationFromMusic { music_token } => {
+            let resp = sess.create_station_from_music_token(music_token).await?;
+            trace!("Pandora task: created station from music");
+            let station_name = created_station_name(sess, &resp.station_token).await;
+            Ok(PandoraResult::StationCreated {
+                station_token: resp.station_token,
+                station_name,
+            })
+        }
+        PandoraCommand::DeleteStation(station_id) => {
+            sess.delete_station(station_id).await?;
+            trace!("Pandora task: deleted station");
+            Ok(PandoraResult::StationDeleted(station_id.clone()))
+        }
+        PandoraCommand::RenameStation {
+            station_id,
+            station_name,
+        } => {
+            sess.rename_station(station_id, station_name).await?;
+            trace!("Pandora task: renamed station");
+            Ok(PandoraResult::StationRenamed {
+                station_id: station_id.clone(),
+                station_name: station_name.clone(),
+            })
+        }
+        PandoraCommand::AddArtistSeed {
+            station_id,
+            artist_name,
+        } => {
+            let resp = sess.search(artist_name).await?;
+            let artist = resp.artists.first().ok_or_else(|| {
+                warn!("Pandora task: no artist match for '{artist_name}'");
+                anyhow::anyhow!("No artist found for '{artist_name}'")
+            })?;
+            let resp = sess.add_music(station_id, &artist.music_token).await?;
+            trace!("Pandora task: added artist seed");
+            Ok(PandoraResult::SeedAdded {
+                seed_id: resp.seed_id,
+                artist_name: resp.artist_name,
+            })
+        }
+        PandoraCommand::RemoveSeed(seed_id) => {
+            sess.delete_music(seed_id).await?;
+            trace!("Pandora task: removed seed");
+            Ok(PandoraResult::SeedRemoved)
+        }
+        PandoraCommand::Search(text) => {
+            let results = sess.search_music(text).await?;
+            trace!(
+                "Pandora task: {} matches for '{text}'",
+                results.matches.len()
+            );
+            Ok(PandoraResult::SearchResults(results))
+        }
+        PandoraCommand::GetGenreStations => {
+            let categories = genres::get_genre_categories(sess).await?;
+            trace!("Pandora task: {} genre categories", categories.len());
+            Ok(PandoraResult::GenreStations(categories))
+        }
+        PandoraCommand::SetQuickMix(station_ids) => {
+            sess.set_quick_mix(station_ids.clone()).await?;
+            trace!("Pandora task: set quickmix stations");
+            Ok(PandoraResult::QuickMixSet(station_ids.clone()))
+        }
+        PandoraCommand::AddBookmark {
+            track_token,
+            as_artist,
+        } => {
+            if *as_artist {
+                sess.add_artist_bookmark(track_token).await?;
+            } else {
+                sess.add_song_bookmark(track_token).await?;
+            }
+            trace!("Pandora task: added bookmark");
+            Ok(PandoraResult::BookmarkAdded)
+        }
+        PandoraCommand::SleepSong(track_token) => {
+            sess.sleep_song(track_token).await?;
+            trace!("Pandora task: slept song");
+            Ok(PandoraResult::SongSlept(track_token.clone()))
+        }
+        PandoraCommand::GetBookmarks => {
+            let bookmarks = sess.get_bookmarked_music().await?;
+            trace!("Pandora task: {} bookmarks", bookmarks.len());
+            Ok(PandoraResult::Bookmarks(bookmarks))
+        }
+        PandoraCommand::ShareStation { station_id, emails } => {
+            // The station token and id are the same thing for stations in our list
+            sess.share_station(station_id, station_id, emails.clone())
+                .await?;
+            trace!("Pandora task: shared station");
+            Ok(PandoraResult::StationShared(station_id.clone()))
+        }
+        PandoraCommand::AcceptSharedStation(music_token) => {
+            let resp = sess.create_station_from_music_token(music_token).await?;
+            trace!("Pandora task: added shared station");
+            // Shared stations can't be rated or have their seeds changed until
+            // they've been transformed into one of our own
+            if let Err(e) = sess.transform_shared_station(&resp.station_token).await {
+                warn!("Pandora task: failed transforming shared station: {e:#}");
+                return Ok(PandoraResult::Error(
+                    Error::PandoraSharedStationNotTransformed(resp.station_token, format!("{e:#}")),
+                ));
             }
+            let station_name = created_station_name(sess, &resp.station_token).await;
+            Ok(PandoraResult::StationCreated {
+                station_token: resp.station_token,
+                station_name,
+            })
+        }
+        PandoraCommand::GetUsageInfo => {
+            let usage = get_usage_info(sess).await?;
+            trace!("Pandora task: usage {usage}");
+            Ok(PandoraResult::UsageInfo(usage))
+        }
+        PandoraCommand::ExplainTrack(track_token) => {
+            let resp = sess.explain_track(track_token).await?;
+            let traits = resp
+                .explanations
+                .into_iter()
+                .map(|e| e.focus_trait_name)
+                .collect();
+            Ok(PandoraResult::TrackExplanation {
+                track_token: track_token.clone(),
+                traits,
+            })
         }
+        PandoraCommand::Connect | PandoraCommand::Disconnect | PandoraCommand::Quit => Err(
+            anyhow::anyhow!(Error::invalid_operation_for_state(cmd.name(), "Connected")),
+        ),
+    }
+}
+
+async fn get_usage_info(sess: &mut PandoraSession) -> Result<UsageInfo> {
+    let resp = sess.get_usage_info().await?;
+    Ok(UsageInfo {
+        listening_hours: resp.account_monthly_listening,
+        cap_hours: resp.monthly_cap_hours,
+        capped: resp.is_capped,
+    })
+}
+
+/// Log in and fetch the account usage info, outside of the Pandora task (for
+/// printing it from the command line).
+pub(crate) async fn fetch_usage_info(config: SharedConfig) -> Result<UsageInfo> {
+    let mut session = PandoraSession::new(config)?;
+    do_connect(&mut session)
+        .await
+        .map_err(|e| anyhow::anyhow!(auth_failure_message(&e)))?;
+    let usage = get_usage_info(&mut session).await;
+    session.partner_logout().await;
+    usage
+}
+
+/// Look up the name Pandora gave a newly-created station. An empty name is
+/// returned if the lookup fails, and the model falls back to reloading the
+/// station list.
+async fn created_station_name(sess: &mut PandoraSession, station_token: &str) -> String {
+    match sess.get_station(station_token, false).await {
+        Ok(resp) => resp.station_name,
+        Err(e) => {
+            warn!("Pandora task: failed looking up name of new station {station_token}: {e:#}");
+            String::new()
+        }
+    }
+}
+
+fn auth_failure_message(e: &anyhow::Error) -> String {
+    if e.downcast_ref::<Error>()
+        .is_some_and(|e| e.missing_auth_token())
+    {
+        String::from("Required authentication token is missing.")
+    } else if let Some(e) = e.downcast_ref::<pandora_api::errors::Error>() {
+        format!("Pandora authentication failure: {e:#}")
+    } else {
+        format!("Unknown error while logging in: {e:#}")
     }
 }
 
@@ -515,3 +1315,537 @@ async fn do_connect(session: &mut PandoraSession) -> Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AudioQuality, Credentials, NetworkSettings, PartialConfig};
+    use crate::pandora::mock::{self, MockPandora};
+
+    const RESULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Unwrap a result of the expected kind, failing the test on any other.
+    macro_rules! expect_result {
+        ($result:expr, $pattern:pat => $value:expr) => {
+            match $result {
+                $pattern => $value,
+                other => panic!("unexpected result {other:?}"),
+            }
+        };
+    }
+
+    struct TaskChannels {
+        command_tx: mpsc::Sender<TaggedCommand>,
+        result_rx: mpsc::Receiver<TaggedResult>,
+        next_id: CommandId,
+    }
+
+    impl TaskChannels {
+        fn start(config: SharedConfig) -> Self {
+            let (command_tx, command_rx) = mpsc::channel(8);
+            let (result_tx, result_rx) = mpsc::channel(8);
+            tokio::spawn(run_pandora_task(config, command_rx, result_tx));
+            Self {
+                command_tx,
+                result_rx,
+                next_id: 0,
+            }
+        }
+
+        async fn send(&mut self, command: PandoraCommand) -> CommandId {
+            self.next_id += 1;
+            let id = self.next_id;
+            self.command_tx
+                .send(TaggedCommand { id, command })
+                .await
+                .expect("pandora task running");
+            id
+        }
+
+        /// The next result for a command we sent, skipping over the results
+        /// of any background refreshes.
+        async fn next_result(&mut self) -> (CommandId, PandoraResult) {
+            loop {
+                let tagged = tokio::time::timeout(RESULT_TIMEOUT, self.result_rx.recv())
+                    .await
+                    .expect("timed out waiting for pandora task")
+                    .expect("pandora task running");
+                if let Some(id) = tagged.id {
+                    return (id, tagged.result);
+                }
+            }
+        }
+
+        async fn request(&mut self, command: PandoraCommand) -> PandoraResult {
+            let id = self.send(command).await;
+            let (result_id, result) = self.next_result().await;
+            assert_eq!(result_id, id, "result for another command: {result:?}");
+            result
+        }
+
+        async fn connect(&mut self) {
+            let result = self.request(PandoraCommand::Connect).await;
+            assert!(
+                matches!(result, PandoraResult::Connected),
+                "unexpected result {result:?}"
+            );
+        }
+
+        async fn playlist(&mut self, station_id: &str) -> Vec<Track> {
+            expect_result!(
+                self.request(PandoraCommand::GetPlaylist(station_id.to_string()))
+                    .await,
+                PandoraResult::Playlist(tracks) => tracks
+            )
+        }
+    }
+
+    #[tokio::test]
+    async fn connect_and_list_stations() {
+        let mock = MockPandora::start().await;
+        let mut task = TaskChannels::start(mock.config());
+        task.connect().await;
+        let (list, quickmix) = expect_result!(
+            task.request(PandoraCommand::GetStationList).await,
+            PandoraResult::StationList(list, quickmix) => (list, quickmix)
+        );
+        assert_eq!(list.len(), 3);
+        assert_eq!(
+            list.get("101").map(|s| s.station_name.as_str()),
+            Some("Mock Rock Radio")
+        );
+        assert_eq!(quickmix.station_id.as_deref(), Some("100"));
+        assert_eq!(quickmix.station_ids, vec![String::from("101")]);
+    }
+
+    #[tokio::test]
+    async fn wrong_password_fails_login() {
+        let mock = MockPandora::start().await;
+        let config = mock.config();
+        config
+            .write()
+            .expect("config write for test login")
+            .update_from(&PartialConfig::default().login(Credentials::ConfigFile(
+                mock::USERNAME.to_string(),
+                String::from("wrong"),
+            )));
+        let mut task = TaskChannels::start(config);
+        let result = task.request(PandoraCommand::Connect).await;
+        assert!(
+            matches!(result, PandoraResult::AuthFailed(_)),
+            "unexpected result {result:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_network_settings_are_a_config_error() {
+        let mock = MockPandora::start().await;
+        let config = mock.config();
+        let network = NetworkSettings {
+            proxy: Some(String::from("http://[::1")),
+            ..NetworkSettings::default()
+        };
+        config
+            .write()
+            .expect("config write for test network")
+            .update_from(&PartialConfig {
+                network: Some(network),
+                ..PartialConfig::default()
+            });
+        let mut task = TaskChannels::start(config);
+        let result = task.request(PandoraCommand::Connect).await;
+        assert!(
+            matches!(result, PandoraResult::InvalidConfig(_)),
+            "unexpected result {result:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn refresh_station_list_only_when_changed() {
+        let mock = MockPandora::start().await;
+        let mut task = TaskChannels::start(mock.config());
+        task.connect().await;
+        task.request(PandoraCommand::GetStationList).await;
+        let result = task.request(PandoraCommand::RefreshStationList).await;
+        assert!(
+            matches!(result, PandoraResult::StationListCurrent),
+            "unexpected result {result:?}"
+        );
+
+        let station_id = mock.add_station("Mock Folk Radio");
+        let list = expect_result!(
+            task.request(PandoraCommand::RefreshStationList).await,
+            PandoraResult::StationList(list, _) => list
+        );
+        assert_eq!(
+            list.get(&station_id).map(|s| s.station_name.as_str()),
+            Some("Mock Folk Radio")
+        );
+    }
+
+    #[tokio::test]
+    async fn playlist_prefers_configured_audio_quality() {
+        let mock = MockPandora::start().await;
+        let config = mock.config();
+        config.write().expect("config write for test").audio_quality = AudioQuality::Low;
+        let mut task = TaskChannels::start(config);
+        task.connect().await;
+        let tracks = task.playlist("101").await;
+        assert!(!tracks.is_empty());
+        for track in tracks {
+            assert_eq!(track.station_id, "101");
+            assert_eq!(track.track_gain, Some(-4.59));
+            let qualities: Vec<AudioQuality> =
+                track.audio_streams.iter().map(|(q, _)| *q).collect();
+            assert_eq!(
+                qualities,
+                vec![AudioQuality::Low, AudioQuality::Medium, AudioQuality::High]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn rate_and_remove_rating() {
+        let mock = MockPandora::start().await;
+        let mut task = TaskChannels::start(mock.config());
+        task.connect().await;
+        let track = task.playlist("101").await.remove(0);
+        let result = task
+            .request(PandoraCommand::RateTrack(track.clone(), Some(true)))
+            .await;
+        assert!(
+            matches!(result, PandoraResult::Rated(1)),
+            "unexpected result {result:?}"
+        );
+
+        let rated = expect_result!(
+            task.request(PandoraCommand::ListRatedTracks(String::from("101")))
+                .await,
+            PandoraResult::RatedTracks(_, rated) => rated
+        );
+        assert!(rated.thumbs_down.is_empty());
+        let [loved] = rated.thumbs_up.as_slice() else {
+            panic!("expected one rated track, got {:?}", rated.thumbs_up);
+        };
+        assert_eq!(loved.song_name, track.title);
+
+        let result = task
+            .request(PandoraCommand::DeleteFeedback {
+                station_id: String::from("101"),
+                feedback_id: loved.feedback_id.clone(),
+                music_token: loved.music_token.clone(),
+            })
+            .await;
+        assert!(
+            matches!(result, PandoraResult::FeedbackDeleted { .. }),
+            "unexpected result {result:?}"
+        );
+        let rated = expect_result!(
+            task.request(PandoraCommand::ListRatedTracks(String::from("101")))
+                .await,
+            PandoraResult::RatedTracks(_, rated) => rated
+        );
+        assert!(rated.thumbs_up.is_empty());
+    }
+
+    #[tokio::test]
+    async fn sleep_song() {
+        let mock = MockPandora::start().await;
+        let mut task = TaskChannels::start(mock.config());
+        task.connect().await;
+        let track = task.playlist("101").await.remove(0);
+        let track_token = expect_result!(
+            task.request(PandoraCommand::SleepSong(track.track_token.clone()))
+                .await,
+            PandoraResult::SongSlept(track_token) => track_token
+        );
+        assert_eq!(track_token, track.track_token);
+    }
+
+    #[tokio::test]
+    async fn rating_is_not_held_up_by_a_stalled_playlist() {
+        let mock = MockPandora::start().await;
+        let mut task = TaskChannels::start(mock.config());
+        task.connect().await;
+        let track = task.playlist("101").await.remove(0);
+        mock.set_playlist_delay(Some(Duration::from_secs(2)));
+
+        let playlist_id = task
+            .send(PandoraCommand::GetPlaylist(String::from("101")))
+            .await;
+        let rating_id = task
+            .send(PandoraCommand::RateTrack(track, Some(true)))
+            .await;
+        let (id, result) = task.next_result().await;
+        assert_eq!(id, rating_id, "unexpected result {result:?}");
+        assert!(
+            matches!(result, PandoraResult::Rated(1)),
+            "unexpected result {result:?}"
+        );
+        let (id, result) = task.next_result().await;
+        assert_eq!(id, playlist_id, "unexpected result {result:?}");
+        assert!(
+            matches!(result, PandoraResult::Playlist(_)),
+            "unexpected result {result:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn commands_for_a_station_run_in_order() {
+        let mock = MockPandora::start().await;
+        let mut task = TaskChannels::start(mock.config());
+        task.connect().await;
+        let add_id = task
+            .send(PandoraCommand::AddSeed {
+                station_id: String::from("102"),
+                music_token: String::from("R2"),
+            })
+            .await;
+        let list_id = task
+            .send(PandoraCommand::ListSeeds(String::from("102")))
+            .await;
+        let (id, result) = task.next_result().await;
+        assert_eq!(id, add_id, "unexpected result {result:?}");
+        let (id, result) = task.next_result().await;
+        assert_eq!(id, list_id, "unexpected result {result:?}");
+        let seeds = expect_result!(result, PandoraResult::Seeds(_, seeds) => seeds);
+        assert!(seeds.artist_seeds.iter().any(|s| s.music_token == "R2"));
+    }
+
+    #[tokio::test]
+    async fn add_and_list_seeds() {
+        let mock = MockPandora::start().await;
+        let mut task = TaskChannels::start(mock.config());
+        task.connect().await;
+        let result = task
+            .request(PandoraCommand::AddSeed {
+                station_id: String::from("102"),
+                music_token: String::from("R2"),
+            })
+            .await;
+        assert!(
+            matches!(result, PandoraResult::SeedAdded { .. }),
+            "unexpected result {result:?}"
+        );
+        let (station_id, seeds) = expect_result!(
+            task.request(PandoraCommand::ListSeeds(String::from("102")))
+                .await,
+            PandoraResult::Seeds(station_id, seeds) => (station_id, seeds)
+        );
+        assert_eq!(station_id, "102");
+        assert!(seeds.artist_seeds.iter().any(|s| s.music_token == "R2"));
+    }
+
+    #[tokio::test]
+    async fn station_details() {
+        let mock = MockPandora::start().await;
+        let mut task = TaskChannels::start(mock.config());
+        task.connect().await;
+        let track = task.playlist("101").await.remove(0);
+        task.request(PandoraCommand::RateTrack(track, Some(true)))
+            .await;
+        let details = expect_result!(
+            task.request(PandoraCommand::GetStationDetails(String::from("101")))
+                .await,
+            PandoraResult::StationDetails(details) => details
+        );
+        assert_eq!(details.station.station_name, "Mock Rock Radio");
+        assert_eq!(details.station.genres, vec![String::from("Mock Genre")]);
+        assert_eq!(details.station.art_url, None);
+        assert_eq!(
+            details.station.date_created,
+            chrono::NaiveDate::from_ymd_opt(2023, 11, 14)
+        );
+        assert!(details
+            .seeds
+            .artist_seeds
+            .iter()
+            .any(|s| s.music_token == "R1"));
+        assert_eq!((details.thumbs_up, details.thumbs_down), (1, 0));
+    }
+
+    #[tokio::test]
+    async fn missing_station_is_reported_as_unavailable() {
+        let mock = MockPandora::start().await;
+        let mut task = TaskChannels::start(mock.config());
+        task.connect().await;
+        let result = task
+            .request(PandoraCommand::GetPlaylist(String::from("999")))
+            .await;
+        assert!(
+            matches!(&result, PandoraResult::Error(Error::PandoraStationUnavailable(id)) if id == "999"),
+            "unexpected result {result:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn unreachable_endpoint_is_not_an_auth_failure() {
+        let mock = MockPandora::start().await;
+        let config = mock.config();
+        // Grab a free port, and leave nothing listening on it
+        let addr = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind unused port")
+            .local_addr()
+            .expect("unused port address");
+        config
+            .write()
+            .expect("config write for test endpoint")
+            .partner
+            .endpoint = format!("http://{addr}/services/json");
+        let mut task = TaskChannels::start(config);
+        let result = task.request(PandoraCommand::Connect).await;
+        assert!(
+            matches!(&result, PandoraResult::Retrying { what, .. } if what == "login"),
+            "unexpected result {result:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn throttled_playlist_waits_out_cooldown() {
+        let mock = MockPandora::start().await;
+        let mut task = TaskChannels::start(mock.config());
+        task.connect().await;
+        mock.set_playlists_throttled(true);
+        let result = task
+            .request(PandoraCommand::GetPlaylist(String::from("101")))
+            .await;
+        let (what, delay) = expect_result!(
+            result,
+            PandoraResult::Retrying { what, delay } => (what, delay)
+        );
+        assert_eq!(what, "playlist");
+        assert!(
+            delay >= THROTTLE_COOLDOWN * 3 / 4,
+            "retry in {delay:?} is inside the cooldown"
+        );
+    }
+
+    #[tokio::test]
+    async fn connect_drops_pending_retries() {
+        let mock = MockPandora::start().await;
+        let (result_tx, mut result_rx) = mpsc::channel(8);
+        let mut task = PandoraTask::new(mock.config(), result_tx);
+        task.defer(
+            Some(1),
+            PandoraCommand::GetPlaylist(String::from("101")),
+            false,
+            1,
+            THROTTLE_COOLDOWN,
+            Some(Error::PandoraRateLimited(String::from("throttled"))),
+        );
+        tokio::time::timeout(
+            RESULT_TIMEOUT,
+            task.handle(Some(2), PandoraCommand::Connect),
+        )
+        .await
+        .expect("connect waited on the retry");
+
+        let result = result_rx.recv().await.expect("playlist result");
+        assert_eq!(result.id, Some(1));
+        assert!(
+            matches!(
+                result.result,
+                PandoraResult::Error(Error::PandoraRateLimited(_))
+            ),
+            "unexpected result {:?}",
+            result.result
+        );
+        let result = result_rx.recv().await.expect("connect result");
+        assert_eq!(result.id, Some(2));
+        assert!(
+            matches!(result.result, PandoraResult::Connected),
+            "unexpected result {:?}",
+            result.result
+        );
+        assert!(task.next_deferred_at().is_none());
+    }
+
+    #[tokio::test]
+    async fn queued_commands_wait_for_connect() {
+        let mock = MockPandora::start().await;
+        let (result_tx, mut result_rx) = mpsc::channel(8);
+        let mut task = PandoraTask::new(mock.config(), result_tx);
+        let details = PandoraCommand::GetStationDetails(String::from("101"));
+        task.cooldowns.insert(
+            CommandClass::of(&details),
+            Instant::now() + THROTTLE_COOLDOWN,
+        );
+        task.submit(Some(1), details.clone());
+        task.submit(Some(2), details);
+        task.cooldowns.clear();
+        tokio::time::timeout(
+            RESULT_TIMEOUT,
+            task.handle(Some(3), PandoraCommand::Connect),
+        )
+        .await
+        .expect("connect finished");
+
+        let result = result_rx.recv().await.expect("dropped command result");
+        assert_eq!(result.id, Some(1));
+        assert!(
+            matches!(result.result, PandoraResult::Cancelled),
+            "unexpected result {:?}",
+            result.result
+        );
+        let result = result_rx.recv().await.expect("connect result");
+        assert_eq!(result.id, Some(3));
+        assert!(
+            matches!(result.result, PandoraResult::Connected),
+            "unexpected result {:?}",
+            result.result
+        );
+
+        // The command queued behind the dropped one only runs once logged in
+        let joined = tokio::time::timeout(RESULT_TIMEOUT, task.in_flight.join_next())
+            .await
+            .expect("queued command started")
+            .expect("queued command in flight");
+        task.finished(joined).await;
+        let result = result_rx.recv().await.expect("queued command result");
+        assert_eq!(result.id, Some(2));
+        assert!(
+            matches!(result.result, PandoraResult::StationDetails(_)),
+            "unexpected result {:?}",
+            result.result
+        );
+    }
+
+    #[tokio::test]
+    async fn disconnect_cancels_running_commands() {
+        let mock = MockPandora::start().await;
+        let mut task = TaskChannels::start(mock.config());
+        task.connect().await;
+        mock.set_playlist_delay(Some(RESULT_TIMEOUT));
+        let playlist_id = task
+            .send(PandoraCommand::GetPlaylist(String::from("101")))
+            .await;
+        let disconnect_id = task.send(PandoraCommand::Disconnect).await;
+
+        let (id, result) = task.next_result().await;
+        assert_eq!(id, playlist_id);
+        assert!(
+            matches!(result, PandoraResult::Cancelled),
+            "unexpected result {result:?}"
+        );
+        let (id, result) = task.next_result().await;
+        assert_eq!(id, disconnect_id);
+        assert!(
+            matches!(result, PandoraResult::Disconnected),
+            "unexpected result {result:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn expired_session_logs_in_again() {
+        let mock = MockPandora::start().await;
+        let mut task = TaskChannels::start(mock.config());
+        task.connect().await;
+        mock.expire_sessions();
+        let result = task.request(PandoraCommand::GetStationList).await;
+        assert!(
+            matches!(result, PandoraResult::StationList(_, _)),
+            "unexpected result {result:?}"
+        );
+    }
+}
@@ -7,6 +7,7 @@ use redlux::rodio;
 use rodio::stream::DeviceSinkBuilder;
 use rodio::Source;
 
+use crate::config::{ReplayGainSettings, SharedConfig};
 use crate::messages::{Request, State, StopReason};
 use crate::model::{RequestSender, StateReceiver};
 use crate::track::Track;
@@ -46,12 +47,24 @@ impl Default for Volume {
     }
 }
 
+/// The factor to scale a track's volume by for its ReplayGain adjustment (in
+/// dB), plus the pre-amp.  Tracks without an adjustment only get the pre-amp.
+fn replay_gain_factor(track_gain: Option<f32>, settings: &ReplayGainSettings) -> f32 {
+    if !settings.enabled {
+        return 1.0f32;
+    }
+    let gain_db = track_gain.unwrap_or_default() + settings.preamp_db();
+    10.0f32.powf(gain_db / 20.0)
+}
+
 // We can't derive Debug or Clone since the rodio members don't implement it.
 // rodio 0.22: MixerDeviceSink holds the stream; Player is the sequential sink.
 struct AudioDevice {
     _handle: rodio::stream::MixerDeviceSink,
     player: rodio::Player,
     volume: Volume,
+    /// ReplayGain factor for the playing track
+    gain: f32,
 }
 
 impl AudioDevice {
@@ -63,6 +76,7 @@ impl AudioDevice {
             _handle: handle,
             player,
             volume: Volume::Unmuted(volume),
+            gain: 1.0f32,
         }
     }
 
@@ -95,12 +109,14 @@ impl AudioDevice {
     fn play_from_source(
         &mut self,
         source: redlux::Decoder<BufReader<std::fs::File>>,
+        gain: f32,
     ) -> Result<()> {
         self.reset();
+        self.gain = gain;
 
         let start_paused = false;
         self.player.append(source.pausable(start_paused));
-        self.player.set_volume(self.volume.volume());
+        self.refresh_volume();
         self.player.play();
         Ok(())
     }
@@ -123,7 +139,7 @@ impl AudioDevice {
 
     fn reset(&mut self) {
         self.player.clear();
-        self.player.set_volume(self.volume.volume());
+        self.refresh_volume();
     }
 
     fn active(&self) -> bool {
@@ -144,7 +160,9 @@ impl AudioDevice {
     }
 
     fn refresh_volume(&mut self) {
-        self.player.set_volume(self.volume.volume());
+        // Boosting quiet tracks is limited to full volume, so that they don't clip
+        self.player
+            .set_volume((self.volume.volume() * self.gain).clamp(0.0f32, 1.0f32));
     }
 
     fn mute(&mut self) {
@@ -168,6 +186,7 @@ impl Clone for AudioDevice {
             _handle: handle,
             player,
             volume: self.volume,
+            gain: self.gain,
         }
     }
 }
@@ -182,11 +201,12 @@ impl std::fmt::Debug for AudioDevice {
         };
         write!(
             f,
-            "AudioDevice {{ player: ({}, {}, volume {:.2}), volume: {:?} }}",
+            "AudioDevice {{ player: ({}, {}, volume {:.2}), volume: {:?}, gain: {:.2} }}",
             queued,
             paused,
             self.player.volume(),
-            self.volume
+            self.volume,
+            self.gain
         )
     }
 }
@@ -199,6 +219,7 @@ impl Default for AudioDevice {
 
 #[derive(Debug, Clone)]
 pub(crate) struct Player {
+    config: SharedConfig,
     active_track: Option<Track>,
     audio_device: AudioDevice,
     last_started: Option<Instant>,
@@ -211,8 +232,13 @@ pub(crate) struct Player {
 }
 
 impl Player {
-    pub(crate) fn new(state_receiver: StateReceiver, request_sender: RequestSender) -> Self {
+    pub(crate) fn new(
+        config: SharedConfig,
+        state_receiver: StateReceiver,
+        request_sender: RequestSender,
+    ) -> Self {
         Self {
+            config,
             active_track: None,
             audio_device: AudioDevice::new(0.0),
             last_started: None,
@@ -248,10 +274,21 @@ impl Player {
         }
 
         debug!("Starting track: {:?}", track.title);
+        let gain = replay_gain_factor(
+            track.track_gain,
+            self.config
+                .read()
+                .expect("config read for replay gain")
+                .replay_gain(),
+        );
+        debug!(
+            "ReplayGain for track: {:?} dB (x{gain:.2})",
+            track.track_gain
+        );
         trace!("Starting decoding of track {}", track.cache_path.display());
         if let Err(e) = track
             .get_m4a_decoder()
-            .and_then(|dec| self.audio_device.play_from_source(dec))
+            .and_then(|dec| self.audio_device.play_from_source(dec, gain))
         {
             error!(
                 "Failed to start track at {}: {e:#}",
@@ -1,11 +1,14 @@
-use cursive::views::{DummyView, EditView, HideableView, LinearLayout, ResizedView, SelectView};
+use cursive::views::{
+    Checkbox, DummyView, EditView, HideableView, LinearLayout, ResizedView, SelectView, TextView,
+};
 use cursive::Cursive;
 use cursive::View;
 use log::{error, trace};
 
 use crate::messages::{Request, StopReason};
-use crate::term_ui::dialogs::Store;
-use crate::term_ui::TerminalContext;
+use crate::pandora::{GenreStation, MusicMatch, RatedTrackInfo};
+use crate::term_ui::dialogs::{self, Store};
+use crate::term_ui::{station_name, TerminalContext};
 
 use crate::config::PartialConfig;
 
@@ -127,6 +130,459 @@ pub(crate) fn remove_artist_seed(s: &mut Cursive) {
     });
 }
 
+pub(crate) fn edit_seeds(s: &mut Cursive) {
+    if s.find_name::<EditView>("search_text").is_some() {
+        return;
+    }
+    let seed_list = s
+        .with_user_data(|ctx: &mut TerminalContext| {
+            ctx.station_seeds
+                .as_ref()
+                .map(|seeds| seeds.seed_list.clone())
+        })
+        .flatten()
+        .unwrap_or_default();
+    trace!("Activating seed manager dialog");
+    s.add_layer(dialogs::seed_manager_dialog(seed_list));
+}
+
+pub(crate) fn remove_seed_button(s: &mut Cursive) {
+    let seed_id = s
+        .call_on_name("station_seeds", |v: &mut SelectView<String>| {
+            v.selection().map(|id| (*id).clone())
+        })
+        .flatten();
+    if let Some(id) = seed_id {
+        s.with_user_data(|ctx: &mut TerminalContext| {
+            trace!("send request 'remove seed'");
+            let _ = ctx.publish_request(Request::RemoveSeed(id));
+        });
+    }
+}
+
+pub(crate) fn add_seed_button(s: &mut Cursive) {
+    if let Some(music) = selected_search_result(s) {
+        s.with_user_data(|ctx: &mut TerminalContext| {
+            trace!("send request 'add seed'");
+            let _ = ctx.publish_request(Request::AddSeed(music.music_token().to_string()));
+        });
+    }
+}
+
+pub(crate) fn explain_track(s: &mut Cursive) {
+    if s.find_name::<TextView>("track_explanation").is_some() {
+        return;
+    }
+    let Some(track) = s
+        .with_user_data(|ctx: &mut TerminalContext| ctx.active_track.clone())
+        .flatten()
+    else {
+        return;
+    };
+    s.with_user_data(|ctx: &mut TerminalContext| {
+        trace!("send request 'explain track'");
+        let _ = ctx.publish_request(Request::ExplainTrack);
+    });
+    trace!("Activating track explanation dialog");
+    s.add_layer(dialogs::explain_track_dialog(&track));
+}
+
+pub(crate) fn sleep_track(s: &mut Cursive) {
+    s.with_user_data(|ctx: &mut TerminalContext| {
+        trace!("send request 'sleep track'");
+        let _ = ctx.publish_request(Request::SleepTrack);
+    });
+}
+
+pub(crate) fn bookmark_song(s: &mut Cursive) {
+    s.with_user_data(|ctx: &mut TerminalContext| {
+        trace!("send request 'bookmark song'");
+        let _ = ctx.publish_request(Request::BookmarkSong);
+    });
+}
+
+pub(crate) fn bookmark_artist(s: &mut Cursive) {
+    s.with_user_data(|ctx: &mut TerminalContext| {
+        trace!("send request 'bookmark artist'");
+        let _ = ctx.publish_request(Request::BookmarkArtist);
+    });
+}
+
+pub(crate) fn bookmarks(s: &mut Cursive) {
+    if s.find_name::<SelectView<MusicMatch>>("bookmarks").is_some() {
+        return;
+    }
+    s.with_user_data(|ctx: &mut TerminalContext| {
+        trace!("send request 'get bookmarks'");
+        let _ = ctx.publish_request(Request::GetBookmarks);
+    });
+    trace!("Activating bookmarks dialog");
+    s.add_layer(dialogs::bookmarks_dialog());
+}
+
+pub(crate) fn create_bookmark_station_button(s: &mut Cursive) {
+    let Some(music) = s
+        .call_on_name("bookmarks", |v: &mut SelectView<MusicMatch>| {
+            v.selection().map(|m| (*m).clone())
+        })
+        .flatten()
+    else {
+        return;
+    };
+    let tune = s
+        .call_on_name("tune_bookmark_station", |v: &mut Checkbox| v.is_checked())
+        .unwrap_or(false);
+    s.with_user_data(|ctx: &mut TerminalContext| {
+        trace!("send request 'create station' from bookmark");
+        let _ = ctx.publish_request(Request::CreateStation {
+            music_token: music.music_token().to_string(),
+            tune,
+        });
+    });
+    s.pop_layer();
+}
+
+pub(crate) fn share_station(s: &mut Cursive) {
+    if s.find_name::<EditView>("share_emails").is_none() {
+        trace!("Activating share station dialog");
+        s.add_layer(dialogs::share_station_dialog());
+    }
+}
+
+pub(crate) fn share_station_button(s: &mut Cursive) {
+    let emails = s
+        .call_on_name("share_emails", |v: &mut EditView| {
+            v.get_content()
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|email| !email.is_empty())
+                .map(String::from)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    if emails.is_empty() {
+        return;
+    }
+    s.with_user_data(|ctx: &mut TerminalContext| {
+        trace!("send request 'share station'");
+        let _ = ctx.publish_request(Request::ShareStation(emails));
+    });
+    s.pop_layer();
+}
+
+pub(crate) fn accept_shared_station(s: &mut Cursive) {
+    if s.find_name::<EditView>("shared_station").is_none() {
+        trace!("Activating add shared station dialog");
+        s.add_layer(dialogs::accept_shared_station_dialog());
+    }
+}
+
+pub(crate) fn accept_shared_station_button(s: &mut Cursive) {
+    let shared = s
+        .call_on_name("shared_station", |v: &mut EditView| {
+            v.get_content().trim().to_string()
+        })
+        .unwrap_or_default();
+    if shared.is_empty() {
+        return;
+    }
+    let tune = s
+        .call_on_name("tune_shared_station", |v: &mut Checkbox| v.is_checked())
+        .unwrap_or(false);
+    s.with_user_data(|ctx: &mut TerminalContext| {
+        trace!("send request 'accept shared station'");
+        let _ = ctx.publish_request(Request::AcceptSharedStation { shared, tune });
+    });
+    s.pop_layer();
+}
+
+pub(crate) fn refresh_usage(s: &mut Cursive) {
+    s.with_user_data(|ctx: &mut TerminalContext| {
+        trace!("send request 'get usage info'");
+        let _ = ctx.publish_request(Request::GetUsageInfo);
+    });
+}
+
+pub(crate) fn create_track_station(s: &mut Cursive) {
+    s.with_user_data(|ctx: &mut TerminalContext| {
+        trace!("send request 'create station from track'");
+        let _ = ctx.publish_request(Request::CreateTrackStation);
+    });
+}
+
+pub(crate) fn create_artist_station(s: &mut Cursive) {
+    s.with_user_data(|ctx: &mut TerminalContext| {
+        trace!("send request 'create station from artist'");
+        let _ = ctx.publish_request(Request::CreateArtistStation);
+    });
+}
+
+pub(crate) fn new_station(s: &mut Cursive) {
+    if s.find_name::<EditView>("search_text").is_none() {
+        trace!("Activating new station dialog");
+        s.add_layer(dialogs::new_station_dialog());
+    }
+}
+
+pub(crate) fn search_button(s: &mut Cursive) {
+    let text = s
+        .call_on_name("search_text", |v: &mut EditView| {
+            v.get_content().to_string()
+        })
+        .unwrap_or_default();
+    search_submit(s, &text);
+}
+
+pub(crate) fn search_submit(s: &mut Cursive, text: &str) {
+    let text = text.trim();
+    if text.is_empty() {
+        return;
+    }
+    s.call_on_name("search_status", |v: &mut TextView| {
+        v.set_content("Searching...");
+    });
+    s.with_user_data(|ctx: &mut TerminalContext| {
+        trace!("send request 'search'");
+        let _ = ctx.publish_request(Request::Search(text.to_string()));
+    });
+}
+
+/// The music match currently highlighted in the search results list.
+fn selected_search_result(s: &mut Cursive) -> Option<MusicMatch> {
+    s.call_on_name("search_results", |v: &mut SelectView<MusicMatch>| {
+        v.selection().map(|m| (*m).clone())
+    })
+    .flatten()
+}
+
+pub(crate) fn create_station_button(s: &mut Cursive) {
+    let Some(music) = selected_search_result(s) else {
+        return;
+    };
+    let tune = s
+        .call_on_name("tune_new_station", |v: &mut Checkbox| v.is_checked())
+        .unwrap_or(false);
+    s.with_user_data(|ctx: &mut TerminalContext| {
+        trace!("send request 'create station'");
+        let _ = ctx.publish_request(Request::CreateStation {
+            music_token: music.music_token().to_string(),
+            tune,
+        });
+    });
+    s.pop_layer();
+}
+
+pub(crate) fn genre_stations(s: &mut Cursive) {
+    if s.find_name::<SelectView<GenreStation>>("genre_stations")
+        .is_some()
+    {
+        return;
+    }
+    s.with_user_data(|ctx: &mut TerminalContext| {
+        trace!("send request 'get genre stations'");
+        let _ = ctx.publish_request(Request::GetGenreStations);
+    });
+    trace!("Activating genre stations dialog");
+    s.add_layer(dialogs::genre_stations_dialog());
+}
+
+pub(crate) fn create_genre_station_button(s: &mut Cursive) {
+    let Some(station) = s
+        .call_on_name("genre_stations", |v: &mut SelectView<GenreStation>| {
+            v.selection().map(|st| (*st).clone())
+        })
+        .flatten()
+    else {
+        return;
+    };
+    let tune = s
+        .call_on_name("tune_genre_station", |v: &mut Checkbox| v.is_checked())
+        .unwrap_or(false);
+    s.with_user_data(|ctx: &mut TerminalContext| {
+        trace!("send request 'create station' from genre");
+        let _ = ctx.publish_request(Request::CreateStation {
+            music_token: station.station_token,
+            tune,
+        });
+    });
+    s.pop_layer();
+}
+
+pub(crate) fn manage_stations(s: &mut Cursive) {
+    if s.find_name::<SelectView<String>>("manage_stations")
+        .is_some()
+    {
+        return;
+    }
+    let stations = s
+        .call_on_name("stations", |v: &mut SelectView<String>| {
+            v.iter()
+                .filter(|(_, id)| !id.is_empty())
+                .map(|(name, id)| (station_name(name), id.clone()))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    trace!("Activating station management dialog");
+    s.add_layer(dialogs::manage_stations_dialog(stations));
+}
+
+pub(crate) fn quickmix(s: &mut Cursive) {
+    if s.find_name::<LinearLayout>("quickmix_stations").is_some() {
+        return;
+    }
+    let Some(quickmix) = s
+        .with_user_data(|ctx: &mut TerminalContext| ctx.quickmix.clone())
+        .filter(|qm| qm.station_id.is_some())
+    else {
+        return;
+    };
+    let stations = s
+        .call_on_name("stations", |v: &mut SelectView<String>| {
+            v.iter()
+                .filter(|(_, id)| !id.is_empty() && quickmix.station_id.as_ref() != Some(*id))
+                .map(|(name, id)| {
+                    (
+                        name.to_string(),
+                        id.clone(),
+                        quickmix.station_ids.contains(id),
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    trace!("Activating quickmix dialog");
+    s.add_layer(dialogs::quickmix_dialog(stations));
+}
+
+pub(crate) fn save_quickmix_button(s: &mut Cursive, station_ids: &[String]) {
+    let selected = station_ids
+        .iter()
+        .filter(|id| {
+            s.call_on_name(&quickmix_checkbox_name(id), |v: &mut Checkbox| {
+                v.is_checked()
+            })
+            .unwrap_or(false)
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    s.with_user_data(|ctx: &mut TerminalContext| {
+        trace!("send request 'set quickmix'");
+        let _ = ctx.publish_request(Request::SetQuickMix(selected));
+    });
+    s.pop_layer();
+}
+
+/// Name of the checkbox for including a station in the QuickMix.
+pub(crate) fn quickmix_checkbox_name(station_id: &str) -> String {
+    format!("quickmix:{station_id}")
+}
+
+/// The (name, id) of the station currently highlighted in the station management dialog.
+fn selected_managed_station(s: &mut Cursive) -> Option<(String, String)> {
+    s.call_on_name("manage_stations", |v: &mut SelectView<String>| {
+        v.selected_id()
+            .and_then(|idx| v.get_item(idx))
+            .map(|(name, id)| (name.to_string(), id.clone()))
+    })
+    .flatten()
+}
+
+pub(crate) fn rename_station_button(s: &mut Cursive) {
+    let Some((_, station_id)) = selected_managed_station(s) else {
+        return;
+    };
+    let station_name = s
+        .call_on_name("station_name", |v: &mut EditView| {
+            v.get_content().trim().to_string()
+        })
+        .unwrap_or_default();
+    if station_name.is_empty() {
+        return;
+    }
+    s.with_user_data(|ctx: &mut TerminalContext| {
+        trace!("send request 'rename station'");
+        let _ = ctx.publish_request(Request::RenameStation {
+            station_id,
+            station_name,
+        });
+    });
+}
+
+pub(crate) fn delete_station_button(s: &mut Cursive) {
+    if let Some((station_name, station_id)) = selected_managed_station(s) {
+        s.add_layer(dialogs::confirm_delete_station_dialog(
+            station_name,
+            station_id,
+        ));
+    }
+}
+
+pub(crate) fn rated_tracks(s: &mut Cursive) {
+    if s.find_name::<SelectView<RatedTrackInfo>>("rated_tracks")
+        .is_some()
+    {
+        return;
+    }
+    let station = s
+        .call_on_name("stations", |v: &mut SelectView<String>| {
+            v.selected_id()
+                .and_then(|idx| v.get_item(idx))
+                .map(|(name, id)| (station_name(name), id.clone()))
+        })
+        .flatten()
+        .filter(|(_, id)| !id.is_empty());
+    let Some((station_name, station_id)) = station else {
+        return;
+    };
+    s.with_user_data(|ctx: &mut TerminalContext| {
+        trace!("send request 'list rated tracks'");
+        let _ = ctx.publish_request(Request::ListRatedTracks(station_id.clone()));
+    });
+    trace!("Activating rated tracks dialog");
+    s.add_layer(dialogs::rated_tracks_dialog(station_name, station_id));
+}
+
+pub(crate) fn station_info(s: &mut Cursive) {
+    if s.find_name::<TextView>("station_info").is_some() {
+        return;
+    }
+    let station = s
+        .call_on_name("stations", |v: &mut SelectView<String>| {
+            v.selected_id()
+                .and_then(|idx| v.get_item(idx))
+                .map(|(name, id)| (station_name(name), id.clone()))
+        })
+        .flatten()
+        .filter(|(_, id)| !id.is_empty());
+    let Some((station_name, station_id)) = station else {
+        return;
+    };
+    s.with_user_data(|ctx: &mut TerminalContext| {
+        trace!("send request 'get station details'");
+        let _ = ctx.publish_request(Request::GetStationDetails(station_id));
+    });
+    trace!("Activating station info dialog");
+    s.add_layer(dialogs::station_info_dialog(station_name));
+}
+
+pub(crate) fn delete_feedback_button(s: &mut Cursive, station_id: &str) {
+    let Some(rated) = s
+        .call_on_name("rated_tracks", |v: &mut SelectView<RatedTrackInfo>| {
+            v.selection().map(|r| (*r).clone())
+        })
+        .flatten()
+    else {
+        return;
+    };
+    s.with_user_data(|ctx: &mut TerminalContext| {
+        trace!("send request 'delete feedback'");
+        let _ = ctx.publish_request(Request::DeleteFeedback {
+            station_id: station_id.to_string(),
+            feedback_id: rated.feedback_id,
+            music_token: rated.music_token,
+        });
+    });
+}
+
 pub(crate) fn connect_button(s: &mut Cursive) {
     let username: Option<String> =
         s.call_on_name("username", |v: &mut EditView| v.get_content().to_string());
@@ -210,6 +666,17 @@ pub(crate) fn ui_scale(s: &mut Cursive) {
         }
     });
 
+    // Hide the account usage if there's less than 7 vertical lines
+    s.call_on_name("usage_hideable", |v: &mut HideableView<LinearLayout>| {
+        if size.y < 7 {
+            v.hide();
+            trace!("Usage hidden.")
+        } else {
+            v.unhide();
+            trace!("Usage unhidden.")
+        }
+    });
+
     // Hide the spacer if there's less than 6 vertical lines
     s.call_on_name(
         "spacer_hideable",
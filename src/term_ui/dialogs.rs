@@ -1,18 +1,20 @@
 use cursive::views::{
-    Button, Dialog, DummyView, EditView, HideableView, LinearLayout, PaddedView, Panel, SelectView,
-    SliderView, TextView,
+    Button, Checkbox, Dialog, DummyView, EditView, HideableView, LinearLayout, ListView,
+    PaddedView, Panel, SelectView, SliderView, TextView,
 };
 use cursive::Cursive;
 use cursive::{theme::ColorStyle, utils::markup::StyledString};
 // Traits pulled in to add methods to types
 use cursive::align::HAlign;
-use cursive::view::{Nameable, Resizable};
+use cursive::view::{Nameable, Resizable, Scrollable};
 
 use log::trace;
 
 use crate::config::{Credentials, SharedConfig};
 use crate::messages::Request;
+use crate::pandora::{GenreStation, MusicMatch, RatedTrackInfo};
 use crate::term_ui::{callbacks, labels, TerminalContext};
+use crate::track::Track;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub(crate) enum Store {
@@ -167,9 +169,19 @@ pub(crate) fn playing_view() -> LinearLayout {
     .title_position(HAlign::Left)
     .with_name("playing");
 
+    let usage_row = LinearLayout::horizontal()
+        .child(
+            TextView::new(StyledString::styled("Usage", ColorStyle::title_secondary()))
+                .fixed_width(9),
+        )
+        .child(TextView::new("...").with_name("usage"))
+        .child(DummyView.min_width(2))
+        .child(TextView::empty().with_name("notice"));
+
     LinearLayout::vertical()
         .child(playing)
         .child(HideableView::new(station_row).with_name("stations_hideable"))
+        .child(HideableView::new(usage_row).with_name("usage_hideable"))
 }
 
 pub(crate) fn login_dialog(config: SharedConfig, message: Option<String>) -> Option<Dialog> {
@@ -240,3 +252,312 @@ pub(crate) fn login_dialog(config: SharedConfig, message: Option<String>) -> Opt
 
     Some(dialog)
 }
+
+/// Search box, and list of search results to select from, shared by the dialogs
+/// that need the user to pick an artist, song or genre.
+fn music_search_view() -> LinearLayout {
+    LinearLayout::vertical()
+        .child(
+            LinearLayout::horizontal()
+                .child(TextView::new("Search:"))
+                .child(PaddedView::lrtb(
+                    1,
+                    1,
+                    0,
+                    0,
+                    EditView::new()
+                        .on_submit(callbacks::search_submit)
+                        .with_name("search_text")
+                        .fixed_width(32),
+                ))
+                .child(Button::new("Search", callbacks::search_button)),
+        )
+        .child(TextView::empty().with_name("search_status"))
+        .child(
+            SelectView::<MusicMatch>::new()
+                .with_name("search_results")
+                .scrollable()
+                .fixed_height(8),
+        )
+}
+
+pub(crate) fn explain_track_dialog(track: &Track) -> Dialog {
+    Dialog::around(
+        LinearLayout::vertical()
+            .child(TextView::new(format!(
+                "{} by {}",
+                track.title, track.artist_name
+            )))
+            .child(DummyView)
+            .child(
+                TextView::new("Loading...")
+                    .with_name("track_explanation")
+                    .scrollable()
+                    .max_height(12),
+            ),
+    )
+    .dismiss_button("Close")
+    .title("Why is this playing?")
+}
+
+pub(crate) fn new_station_dialog() -> Dialog {
+    Dialog::around(
+        LinearLayout::vertical().child(music_search_view()).child(
+            LinearLayout::horizontal()
+                .child(
+                    Checkbox::new()
+                        .with_checked(true)
+                        .with_name("tune_new_station"),
+                )
+                .child(TextView::new(" Tune to new station")),
+        ),
+    )
+    .button("Create", callbacks::create_station_button)
+    .dismiss_button("Cancel")
+    .title("New Station")
+}
+
+pub(crate) fn seed_manager_dialog(seed_list: Vec<(String, String)>) -> Dialog {
+    Dialog::around(
+        LinearLayout::vertical()
+            .child(TextView::new(StyledString::styled(
+                "Seeds",
+                ColorStyle::title_secondary(),
+            )))
+            .child(
+                SelectView::<String>::new()
+                    .with_all(seed_list)
+                    .with_name("station_seeds")
+                    .scrollable()
+                    .fixed_height(8),
+            )
+            .child(Button::new("Remove Seed", callbacks::remove_seed_button))
+            .child(DummyView)
+            .child(music_search_view())
+            .child(Button::new("Add Seed", callbacks::add_seed_button)),
+    )
+    .dismiss_button("Close")
+    .title("Station Seeds")
+}
+
+pub(crate) fn genre_stations_dialog() -> Dialog {
+    let categories = SelectView::<Vec<GenreStation>>::new()
+        .on_select(|s: &mut Cursive, stations: &Vec<GenreStation>| {
+            s.call_on_name("genre_stations", |v: &mut SelectView<GenreStation>| {
+                v.clear();
+                v.add_all(
+                    stations
+                        .iter()
+                        .map(|st| (st.station_name.clone(), st.clone())),
+                );
+            });
+        })
+        .with_name("genre_categories")
+        .scrollable()
+        .fixed_size((24, 12));
+    let stations = SelectView::<GenreStation>::new()
+        .on_submit(|s: &mut Cursive, _: &GenreStation| callbacks::create_genre_station_button(s))
+        .with_name("genre_stations")
+        .scrollable()
+        .fixed_size((32, 12));
+    Dialog::around(
+        LinearLayout::vertical()
+            .child(TextView::new("Loading genres...").with_name("genre_status"))
+            .child(
+                LinearLayout::horizontal()
+                    .child(categories)
+                    .child(DummyView.fixed_width(1))
+                    .child(stations),
+            )
+            .child(
+                LinearLayout::horizontal()
+                    .child(
+                        Checkbox::new()
+                            .with_checked(true)
+                            .with_name("tune_genre_station"),
+                    )
+                    .child(TextView::new(" Tune to new station")),
+            ),
+    )
+    .button("Create", callbacks::create_genre_station_button)
+    .dismiss_button("Cancel")
+    .title("Genre Stations")
+}
+
+/// Stations are (name, id, whether it's in the QuickMix).
+pub(crate) fn quickmix_dialog(stations: Vec<(String, String, bool)>) -> Dialog {
+    let mut list = ListView::new();
+    for (name, id, checked) in &stations {
+        list.add_child(
+            name,
+            Checkbox::new()
+                .with_checked(*checked)
+                .with_name(callbacks::quickmix_checkbox_name(id)),
+        );
+    }
+    let station_ids: Vec<String> = stations.into_iter().map(|(_, id, _)| id).collect();
+    Dialog::around(
+        LinearLayout::vertical()
+            .child(TextView::new("Stations to shuffle between:"))
+            .child(list.scrollable().max_height(16))
+            .with_name("quickmix_stations"),
+    )
+    .button("Save", move |s: &mut Cursive| {
+        callbacks::save_quickmix_button(s, &station_ids)
+    })
+    .dismiss_button("Cancel")
+    .title("Shuffle Stations")
+}
+
+pub(crate) fn bookmarks_dialog() -> Dialog {
+    Dialog::around(
+        LinearLayout::vertical()
+            .child(TextView::new("Loading bookmarks...").with_name("bookmarks_status"))
+            .child(
+                SelectView::<MusicMatch>::new()
+                    .on_submit(|s: &mut Cursive, _: &MusicMatch| {
+                        callbacks::create_bookmark_station_button(s)
+                    })
+                    .with_name("bookmarks")
+                    .scrollable()
+                    .fixed_height(12),
+            )
+            .child(
+                LinearLayout::horizontal()
+                    .child(
+                        Checkbox::new()
+                            .with_checked(true)
+                            .with_name("tune_bookmark_station"),
+                    )
+                    .child(TextView::new(" Tune to new station")),
+            ),
+    )
+    .button("Create Station", callbacks::create_bookmark_station_button)
+    .dismiss_button("Close")
+    .title("Bookmarks")
+}
+
+pub(crate) fn share_station_dialog() -> Dialog {
+    Dialog::around(
+        LinearLayout::vertical()
+            .child(TextView::new(
+                "Email addresses to share the tuned station with:",
+            ))
+            .child(
+                EditView::new()
+                    .on_submit(|s: &mut Cursive, _: &str| callbacks::share_station_button(s))
+                    .with_name("share_emails")
+                    .fixed_width(48),
+            ),
+    )
+    .button("Share", callbacks::share_station_button)
+    .dismiss_button("Cancel")
+    .title("Share Station")
+}
+
+pub(crate) fn accept_shared_station_dialog() -> Dialog {
+    Dialog::around(
+        LinearLayout::vertical()
+            .child(TextView::new("Shared station link or token:"))
+            .child(
+                EditView::new()
+                    .on_submit(|s: &mut Cursive, _: &str| {
+                        callbacks::accept_shared_station_button(s)
+                    })
+                    .with_name("shared_station")
+                    .fixed_width(48),
+            )
+            .child(
+                LinearLayout::horizontal()
+                    .child(
+                        Checkbox::new()
+                            .with_checked(true)
+                            .with_name("tune_shared_station"),
+                    )
+                    .child(TextView::new(" Tune to new station")),
+            ),
+    )
+    .button("Add", callbacks::accept_shared_station_button)
+    .dismiss_button("Cancel")
+    .title("Add Shared Station")
+}
+
+pub(crate) fn manage_stations_dialog(stations: Vec<(String, String)>) -> Dialog {
+    let station_list = SelectView::<String>::new()
+        .with_all(stations)
+        .on_select(|s: &mut Cursive, _: &String| {
+            let name = s
+                .call_on_name("manage_stations", |v: &mut SelectView<String>| {
+                    v.selected_id()
+                        .and_then(|idx| v.get_item(idx))
+                        .map(|(name, _)| name.to_string())
+                })
+                .flatten()
+                .unwrap_or_default();
+            s.call_on_name("station_name", |v: &mut EditView| {
+                v.set_content(name);
+            });
+        })
+        .with_name("manage_stations")
+        .scrollable()
+        .fixed_height(10);
+    Dialog::around(
+        LinearLayout::vertical().child(station_list).child(
+            LinearLayout::horizontal()
+                .child(TextView::new("Name:"))
+                .child(PaddedView::lrtb(
+                    1,
+                    1,
+                    0,
+                    0,
+                    EditView::new().with_name("station_name").fixed_width(32),
+                )),
+        ),
+    )
+    .button("Rename", callbacks::rename_station_button)
+    .button("Delete", callbacks::delete_station_button)
+    .dismiss_button("Close")
+    .title("Manage Stations")
+}
+
+pub(crate) fn confirm_delete_station_dialog(station_name: String, station_id: String) -> Dialog {
+    Dialog::text(format!("Delete station {station_name}?"))
+        .button("Delete", move |s: &mut Cursive| {
+            s.with_user_data(|ctx: &mut TerminalContext| {
+                trace!("send request 'delete station'");
+                let _ = ctx.publish_request(Request::DeleteStation(station_id.clone()));
+            });
+            s.pop_layer();
+        })
+        .dismiss_button("Cancel")
+        .title("Delete Station")
+}
+
+pub(crate) fn station_info_dialog(station_name: String) -> Dialog {
+    Dialog::around(
+        TextView::new("Loading...")
+            .with_name("station_info")
+            .scrollable()
+            .max_height(16),
+    )
+    .dismiss_button("Close")
+    .title(format!("Station Info: {station_name}"))
+}
+
+pub(crate) fn rated_tracks_dialog(station_name: String, station_id: String) -> Dialog {
+    Dialog::around(
+        LinearLayout::vertical()
+            .child(TextView::new("Loading...").with_name("rated_tracks_status"))
+            .child(
+                SelectView::<RatedTrackInfo>::new()
+                    .with_name("rated_tracks")
+                    .scrollable()
+                    .fixed_height(12),
+            ),
+    )
+    .button("Remove Rating", move |s: &mut Cursive| {
+        callbacks::delete_feedback_button(s, &station_id)
+    })
+    .dismiss_button("Close")
+    .title(format!("Rated Tracks: {station_name}"))
+}
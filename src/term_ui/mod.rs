@@ -9,6 +9,10 @@ use log::{debug, trace};
 use crate::config::SharedConfig;
 use crate::messages::{Request, State, StationSeedsForUi, StopReason};
 use crate::model::{RequestSender, StateReceiver};
+use crate::pandora::{
+    GenreCategory, GenreStation, MusicMatch, QuickMix, RatedTrackInfo, SearchResults,
+    StationDetails, StationRatedTracksData, UsageInfo,
+};
 use crate::track::Track;
 
 mod callbacks;
@@ -21,6 +25,7 @@ mod labels {
     pub(crate) const LABEL_THUMBS_UP: &str = " 👍 ";
     pub(crate) const LABEL_THUMBS_DOWN: &str = " 👎 ";
     pub(crate) const LABEL_SEED: &str = "🌱";
+    pub(crate) const LABEL_QUICKMIX: &str = "🔀 ";
 }
 #[cfg(not(feature = "emoji_labels"))]
 mod labels {
@@ -29,6 +34,7 @@ mod labels {
     pub(crate) const LABEL_THUMBS_UP: &str = "|+|";
     pub(crate) const LABEL_THUMBS_DOWN: &str = "|-|";
     pub(crate) const LABEL_SEED: &str = "|S|";
+    pub(crate) const LABEL_QUICKMIX: &str = "[Shuffle] ";
 }
 
 #[derive(Debug, Clone)]
@@ -39,6 +45,8 @@ pub(crate) struct TerminalContext {
     pub(crate) active_track: Option<Track>,
     /// Seeds for current station (for seed indicator and remove callbacks).
     pub(crate) station_seeds: Option<StationSeedsForUi>,
+    /// QuickMix station and its stations (for the QuickMix dialog).
+    pub(crate) quickmix: QuickMix,
 }
 
 impl TerminalContext {
@@ -50,6 +58,73 @@ impl TerminalContext {
     }
 }
 
+/// Station name from a station list label, without any QuickMix marker.
+pub(crate) fn station_name(label: &str) -> String {
+    label
+        .strip_prefix(labels::LABEL_QUICKMIX)
+        .unwrap_or(label)
+        .to_string()
+}
+
+/// Position of the station with the given id in a station list.
+fn station_index(v: &SelectView<String>, id: &str) -> Option<usize> {
+    v.iter()
+        .enumerate()
+        .find(|(_, (_, st_id))| st_id.as_str() == id)
+        .map(|(i, _)| i)
+}
+
+/// List entry for a rated track, marked with its rating.
+fn rated_track_label(rated: &RatedTrackInfo) -> String {
+    let rating = if rated.is_positive {
+        labels::LABEL_THUMBS_UP
+    } else {
+        labels::LABEL_THUMBS_DOWN
+    };
+    format!("{rating} {} by {}", rated.song_name, rated.artist_name)
+}
+
+/// Contents of the station info panel.
+fn station_details_text(details: &StationDetails) -> String {
+    let station = &details.station;
+    let mut lines = Vec::new();
+    if !station.genres.is_empty() {
+        lines.push(format!("Genre: {}", station.genres.join(", ")));
+    }
+    if let Some(date_created) = station.date_created {
+        lines.push(format!("Created: {date_created}"));
+    }
+    if let Some(art_url) = &station.art_url {
+        lines.push(format!("Art: {art_url}"));
+    }
+    lines.push(format!(
+        "Ratings: {} thumbs up, {} thumbs down",
+        details.thumbs_up, details.thumbs_down
+    ));
+    lines.push(String::new());
+    lines.push(String::from("Seeds:"));
+    let seeds = &details.seeds;
+    lines.extend(
+        seeds
+            .artist_seeds
+            .iter()
+            .map(|a| format!("* {}", a.artist_name)),
+    );
+    lines.extend(
+        seeds
+            .song_seeds
+            .iter()
+            .map(|s| format!("* {} by {}", s.song_name, s.artist_name)),
+    );
+    lines.extend(
+        seeds
+            .genre_seeds
+            .iter()
+            .map(|g| format!("* {} (genre)", g.genre_name)),
+    );
+    lines.join("\n")
+}
+
 pub(crate) struct Terminal {
     siv: CursiveRunner<CursiveRunnable>,
     context: TerminalContext,
@@ -57,6 +132,8 @@ pub(crate) struct Terminal {
     active_track: Option<Track>,
     /// Seeds for the current station (song music_tokens, artist names) for seed indicator.
     station_seeds: Option<StationSeedsForUi>,
+    /// QuickMix station, so it can be marked in the station list.
+    quickmix: QuickMix,
     dirty: bool,
 }
 
@@ -72,6 +149,7 @@ impl Terminal {
             request_sender,
             active_track: None,
             station_seeds: None,
+            quickmix: QuickMix::default(),
         };
         siv.set_user_data(context.clone());
         siv.set_fps(10);
@@ -82,6 +160,7 @@ impl Terminal {
             state_receiver,
             active_track: None,
             station_seeds: None,
+            quickmix: QuickMix::default(),
             dirty: true,
         };
         term.initialize();
@@ -117,6 +196,28 @@ impl Terminal {
         self.siv
             .add_global_callback('T', callbacks::remove_track_seed);
         self.siv.add_global_callback('=', callbacks::clear_rating);
+        self.siv.add_global_callback('N', callbacks::new_station);
+        self.siv
+            .add_global_callback('s', callbacks::create_track_station);
+        self.siv
+            .add_global_callback('S', callbacks::create_artist_station);
+        self.siv
+            .add_global_callback('M', callbacks::manage_stations);
+        self.siv.add_global_callback('R', callbacks::rated_tracks);
+        self.siv.add_global_callback('i', callbacks::station_info);
+        self.siv.add_global_callback('E', callbacks::edit_seeds);
+        self.siv.add_global_callback('G', callbacks::genre_stations);
+        self.siv.add_global_callback('w', callbacks::explain_track);
+        self.siv.add_global_callback('Q', callbacks::quickmix);
+        self.siv.add_global_callback('b', callbacks::bookmark_song);
+        self.siv
+            .add_global_callback('B', callbacks::bookmark_artist);
+        self.siv.add_global_callback('K', callbacks::bookmarks);
+        self.siv.add_global_callback('h', callbacks::share_station);
+        self.siv
+            .add_global_callback('H', callbacks::accept_shared_station);
+        self.siv.add_global_callback('u', callbacks::refresh_usage);
+        self.siv.add_global_callback('z', callbacks::sleep_track);
     }
 
     fn init_theme(&mut self) {
@@ -137,6 +238,11 @@ impl Terminal {
 
     fn added_station(&mut self, name: String, id: String) {
         trace!("Adding station {name}[{id}] to list...");
+        let name = if self.quickmix.station_id.as_ref() == Some(&id) {
+            format!("{}{name}", labels::LABEL_QUICKMIX)
+        } else {
+            name
+        };
         self.siv
             .call_on_name("stations", |v: &mut SelectView<String>| {
                 // If we were disconnected, the station list contains one entry: "No Stations"
@@ -175,18 +281,79 @@ impl Terminal {
         self.dirty |= true;
     }
 
+    fn updated_quickmix(&mut self, quickmix: QuickMix) {
+        trace!("Updating quickmix station {:?}...", quickmix.station_id);
+        let old_id = std::mem::replace(&mut self.quickmix, quickmix).station_id;
+        self.sync_context_to_ui();
+        if old_id == self.quickmix.station_id {
+            return;
+        }
+        // Re-add the old and new QuickMix stations so they're labeled correctly
+        for id in old_id.into_iter().chain(self.quickmix.station_id.clone()) {
+            let name = self
+                .siv
+                .call_on_name("stations", |v: &mut SelectView<String>| {
+                    let idx = station_index(v, &id)?;
+                    let name = v.get_item(idx).map(|(name, _)| station_name(name))?;
+                    let _ = v.remove_item(idx);
+                    Some(name)
+                })
+                .flatten();
+            if let Some(name) = name {
+                self.added_station(name, id);
+            }
+        }
+        self.dirty |= true;
+    }
+
+    fn removed_station(&mut self, id: String) {
+        trace!("Removing station {id} from list...");
+        for list in ["stations", "manage_stations"] {
+            self.siv.call_on_name(list, |v: &mut SelectView<String>| {
+                if let Some(idx) = station_index(v, &id) {
+                    let _ = v.remove_item(idx);
+                }
+            });
+        }
+        self.dirty |= true;
+    }
+
+    fn renamed_station(&mut self, name: String, id: String) {
+        trace!("Renaming station {id} to {name}...");
+        let was_selected = self
+            .siv
+            .call_on_name("stations", |v: &mut SelectView<String>| {
+                v.selection().is_some_and(|st_id| *st_id == id)
+            })
+            .unwrap_or(false);
+        self.siv
+            .call_on_name("manage_stations", |v: &mut SelectView<String>| {
+                if let Some(idx) = station_index(v, &id) {
+                    let _ = v.remove_item(idx);
+                    v.insert_item(idx, name.clone(), id.clone());
+                    v.set_selection(idx);
+                }
+            });
+        // Remove and re-add the station so that it's sorted by its new name
+        self.siv
+            .call_on_name("stations", |v: &mut SelectView<String>| {
+                if let Some(idx) = station_index(v, &id) {
+                    let _ = v.remove_item(idx);
+                }
+            });
+        self.added_station(name, id.clone());
+        if was_selected {
+            self.tuned_station(id);
+        }
+    }
+
     fn tuned_station(&mut self, id: String) {
         trace!("Tuning station {id}...");
         self.station_seeds = None;
         self.sync_context_to_ui();
         self.siv
             .call_on_name("stations", |v: &mut SelectView<String>| {
-                let opt_idx = v
-                    .iter()
-                    .enumerate()
-                    .find(|(_, (_, st_id))| *st_id == &id)
-                    .map(|(i, _)| i);
-                if let Some(idx) = opt_idx {
+                if let Some(idx) = station_index(v, &id) {
                     v.set_selection(idx);
                 } else {
                     v.set_selection(0);
@@ -197,6 +364,15 @@ impl Terminal {
 
     fn station_seeds(&mut self, seeds: StationSeedsForUi) {
         let station_id = seeds.station_id.clone();
+        self.siv
+            .call_on_name("station_seeds", |v: &mut SelectView<String>| {
+                let selected = v.selected_id().unwrap_or_default();
+                v.clear();
+                v.add_all(seeds.seed_list.iter().cloned());
+                if !v.is_empty() {
+                    v.set_selection(selected.min(v.len() - 1));
+                }
+            });
         self.station_seeds = Some(seeds);
         self.sync_context_to_ui();
         let to_update = self
@@ -271,6 +447,8 @@ impl Terminal {
         trace!("Updating track info box...");
         self.active_track = Some(track.clone());
         self.sync_context_to_ui();
+        // Playback going again means whatever went wrong has cleared up
+        self.clear_notice();
         let is_track_seed = self.track_is_seed(&track);
         let is_artist_seed = self.artist_is_seed(&track);
         let Track {
@@ -342,6 +520,222 @@ impl Terminal {
         self.dirty |= true;
     }
 
+    fn search_results(&mut self, results: SearchResults) {
+        trace!(
+            "Updating search results for '{}' ({} matches)...",
+            results.query,
+            results.matches.len()
+        );
+        let status = if results.matches.is_empty() {
+            format!("No matches for '{}'", results.query)
+        } else {
+            format!("{} matches for '{}'", results.matches.len(), results.query)
+        };
+        self.siv.call_on_name("search_status", |v: &mut TextView| {
+            v.set_content(status);
+        });
+        self.siv
+            .call_on_name("search_results", |v: &mut SelectView<MusicMatch>| {
+                v.clear();
+                for music in results.matches {
+                    v.add_item(music.to_string(), music);
+                }
+            });
+        self.dirty |= true;
+    }
+
+    fn track_explanation(&mut self, track_token: String, traits: Vec<String>) {
+        if self
+            .active_track
+            .as_ref()
+            .is_none_or(|t| t.track_token != track_token)
+        {
+            trace!("Ignoring explanation for track that isn't playing");
+            return;
+        }
+        let content = if traits.is_empty() {
+            String::from("No explanation available for this track.")
+        } else {
+            traits
+                .iter()
+                .map(|t| format!("* {t}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        self.siv
+            .call_on_name("track_explanation", |v: &mut TextView| {
+                v.set_content(content);
+            });
+        self.dirty |= true;
+    }
+
+    fn show_notice(&mut self, message: String) {
+        trace!("Showing notice: {message}");
+        self.siv.call_on_name("notice", |v: &mut TextView| {
+            v.set_content(StyledString::styled(message, ColorStyle::highlight()));
+        });
+        self.dirty |= true;
+    }
+
+    fn clear_notice(&mut self) {
+        self.siv.call_on_name("notice", |v: &mut TextView| {
+            v.set_content(String::default());
+        });
+    }
+
+    fn usage_info(&mut self, usage: UsageInfo) {
+        trace!("Updating usage info: {usage}");
+        self.siv.call_on_name("usage", |v: &mut TextView| {
+            v.set_content(usage.to_string());
+        });
+        self.dirty |= true;
+    }
+
+    fn bookmarks(&mut self, bookmarks: Vec<MusicMatch>) {
+        trace!("Updating bookmarks ({})...", bookmarks.len());
+        let status = if bookmarks.is_empty() {
+            String::from("No bookmarks")
+        } else {
+            format!("{} bookmarks", bookmarks.len())
+        };
+        self.siv
+            .call_on_name("bookmarks_status", |v: &mut TextView| {
+                v.set_content(status);
+            });
+        self.siv
+            .call_on_name("bookmarks", |v: &mut SelectView<MusicMatch>| {
+                v.clear();
+                for music in bookmarks {
+                    v.add_item(music.to_string(), music);
+                }
+            });
+        self.dirty |= true;
+    }
+
+    fn genre_stations(&mut self, categories: Vec<GenreCategory>) {
+        trace!(
+            "Updating genre stations ({} categories)...",
+            categories.len()
+        );
+        self.siv.call_on_name("genre_status", |v: &mut TextView| {
+            v.set_content(format!("{} genre categories", categories.len()));
+        });
+        let first = categories.first().map(|c| c.stations.clone());
+        self.siv.call_on_name(
+            "genre_categories",
+            |v: &mut SelectView<Vec<GenreStation>>| {
+                v.clear();
+                v.add_all(
+                    categories
+                        .into_iter()
+                        .map(|c| (c.category_name, c.stations)),
+                );
+            },
+        );
+        // Selecting programmatically doesn't fire on_select, so fill in the
+        // stations for the initially-selected category here
+        if let Some(stations) = first {
+            self.siv
+                .call_on_name("genre_stations", |v: &mut SelectView<GenreStation>| {
+                    v.clear();
+                    v.add_all(stations.into_iter().map(|st| (st.station_name.clone(), st)));
+                });
+        }
+        self.dirty |= true;
+    }
+
+    fn rated_tracks(&mut self, station_id: String, data: StationRatedTracksData) {
+        // Only fill in the list if it's for the station the dialog was opened on
+        let selected = self
+            .siv
+            .call_on_name("stations", |v: &mut SelectView<String>| {
+                v.selection().is_some_and(|st_id| *st_id == station_id)
+            })
+            .unwrap_or(false);
+        if !selected {
+            trace!("Ignoring rated tracks for unselected station {station_id}");
+            return;
+        }
+        trace!(
+            "Updating rated tracks ({} up, {} down)...",
+            data.thumbs_up.len(),
+            data.thumbs_down.len()
+        );
+        self.siv
+            .call_on_name("rated_tracks", |v: &mut SelectView<RatedTrackInfo>| {
+                v.clear();
+                for rated in data.thumbs_up.into_iter().chain(data.thumbs_down) {
+                    v.add_item(rated_track_label(&rated), rated);
+                }
+            });
+        self.update_rated_tracks_status();
+        self.dirty |= true;
+    }
+
+    /// Whether the station info panel would be showing this station, the one
+    /// selected when it was opened.
+    fn station_info_shows(&mut self, station_id: &str) -> bool {
+        self.siv
+            .call_on_name("stations", |v: &mut SelectView<String>| {
+                v.selection().is_some_and(|st_id| *st_id == station_id)
+            })
+            .unwrap_or(false)
+    }
+
+    fn station_details(&mut self, details: StationDetails) {
+        let station_id = &details.station.station_id;
+        if !self.station_info_shows(station_id) {
+            trace!("Ignoring details for unselected station {station_id}");
+            return;
+        }
+        let content = station_details_text(&details);
+        self.siv.call_on_name("station_info", |v: &mut TextView| {
+            v.set_content(content);
+        });
+        self.dirty |= true;
+    }
+
+    fn station_details_failed(&mut self, station_id: String, message: String) {
+        if !self.station_info_shows(&station_id) {
+            trace!("Ignoring failed details for unselected station {station_id}");
+            return;
+        }
+        self.siv.call_on_name("station_info", |v: &mut TextView| {
+            v.set_content(format!("Failed to load station details: {message}"));
+        });
+        self.dirty |= true;
+    }
+
+    fn feedback_deleted(&mut self, feedback_id: String) {
+        trace!("Removing feedback {feedback_id} from rated tracks...");
+        self.siv
+            .call_on_name("rated_tracks", |v: &mut SelectView<RatedTrackInfo>| {
+                let opt_idx = v
+                    .iter()
+                    .position(|(_, rated)| rated.feedback_id == feedback_id);
+                if let Some(idx) = opt_idx {
+                    let _ = v.remove_item(idx);
+                }
+            });
+        self.update_rated_tracks_status();
+        self.dirty |= true;
+    }
+
+    fn update_rated_tracks_status(&mut self) {
+        let counts = self
+            .siv
+            .call_on_name("rated_tracks", |v: &mut SelectView<RatedTrackInfo>| {
+                let up = v.iter().filter(|(_, rated)| rated.is_positive).count();
+                (up, v.len() - up)
+            });
+        if let Some((up, down)) = counts {
+            self.siv
+                .call_on_name("rated_tracks_status", |v: &mut TextView| {
+                    v.set_content(format!("{up} thumbs up, {down} thumbs down"));
+                });
+        }
+    }
+
     fn update_state_disconnected(&mut self, message: Option<String>) {
         self.siv
             .call_on_name("playing", |v: &mut Panel<LinearLayout>| {
@@ -421,10 +815,23 @@ impl Terminal {
         while let Ok(message) = self.state_receiver.try_recv() {
             match message {
                 State::AuthFailed(r) => self.update_state_disconnected(Some(r.to_string())),
+                State::ConfigError(message) => {
+                    self.update_state_disconnected(None);
+                    self.show_notice(format!("Configuration error: {message}"));
+                }
+                State::PandoraError(message) => self.show_notice(message),
+                State::Retrying(what, delay) => {
+                    self.show_notice(format!("Retrying {what} in {}s", delay.as_secs()))
+                }
                 State::Connected => self.update_state_stopped(StopReason::Initializing),
                 State::Disconnected => self.update_state_disconnected(None),
-                State::AddStation(name, id) => self.added_station(name, id),
+                State::AddStation(station) => {
+                    self.added_station(station.station_name, station.station_id)
+                }
+                State::RemoveStation(id) => self.removed_station(id),
+                State::RenameStation(name, id) => self.renamed_station(name, id),
                 State::Tuned(name) => self.tuned_station(name),
+                State::QuickMix(quickmix) => self.updated_quickmix(quickmix),
                 State::StationSeeds(seeds) => self.station_seeds(seeds),
                 State::TrackStarting(track) => self.playing_track(track),
                 State::Next(track) => self.next_track(track),
@@ -433,6 +840,17 @@ impl Terminal {
                 State::Paused(elapsed) => self.update_playing(elapsed, true),
                 State::Stopped(r) => self.update_state_stopped(r),
                 State::Buffering => self.update_state_buffering(),
+                State::SearchResults(results) => self.search_results(results),
+                State::UsageInfo(usage) => self.usage_info(usage),
+                State::Bookmarks(bookmarks) => self.bookmarks(bookmarks),
+                State::GenreStations(categories) => self.genre_stations(categories),
+                State::TrackExplanation(token, traits) => self.track_explanation(token, traits),
+                State::RatedTracks(id, data) => self.rated_tracks(id, data),
+                State::StationDetails(details) => self.station_details(details),
+                State::StationDetailsFailed(station_id, message) => {
+                    self.station_details_failed(station_id, message)
+                }
+                State::FeedbackDeleted(id) => self.feedback_deleted(id),
                 State::TrackCaching(_) => (),
                 State::Muted => (),
                 State::Unmuted => (),
@@ -446,6 +864,7 @@ impl Terminal {
     fn sync_context_to_ui(&mut self) {
         self.context.active_track = self.active_track.clone();
         self.context.station_seeds = self.station_seeds.clone();
+        self.context.quickmix = self.quickmix.clone();
         self.siv.set_user_data(self.context.clone());
     }
 
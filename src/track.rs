@@ -5,9 +5,10 @@ use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use anyhow::{Context, Result};
-use log::{debug, error, info, trace};
+use log::{debug, error, info, trace, warn};
 use pandora_api::json::station::PlaylistTrack;
 
+use crate::config::AudioQuality;
 use crate::errors::Error;
 
 #[derive(Debug, Clone)]
@@ -20,8 +21,10 @@ pub(crate) struct Track {
     /// The unique id (token) for the station from which this track was
     /// requested.
     pub station_id: String,
-    /// The url to stream the audio from
-    pub audio_stream: String,
+    /// The urls to stream the audio from, in the order they should be tried
+    pub audio_streams: Vec<(AudioQuality, String)>,
+    /// The quality of the audio that was downloaded, if known
+    pub audio_quality: Option<AudioQuality>,
     /// The name of the artist for this track.
     pub artist_name: String,
     /// The name of the album for this track.
@@ -32,10 +35,25 @@ pub(crate) struct Track {
     pub song_rating: u32,
     /// The track length, if provided
     pub track_length: Duration,
+    /// The ReplayGain adjustment for the track in dB, if provided
+    pub track_gain: Option<f32>,
     /// The path where the song would be cached, if already fetched
     pub cache_path: std::path::PathBuf,
 }
 
+/// The largest ReplayGain adjustment, either way, that a real track would need.
+const MAX_TRACK_GAIN_DB: f32 = 24.0;
+
+/// Read a track's ReplayGain adjustment, which Pandora sends as a string, e.g.
+/// "-4.59".  Anything that isn't a plausible adjustment is ignored.
+fn track_gain(value: &serde_json::Value) -> Option<f32> {
+    value
+        .as_str()
+        .and_then(|g| g.parse().ok())
+        .or_else(|| value.as_f64().map(|g| g as f32))
+        .filter(|g: &f32| g.is_finite() && g.abs() <= MAX_TRACK_GAIN_DB)
+}
+
 impl std::convert::TryFrom<PlaylistTrack> for Track {
     type Error = anyhow::Error;
 
@@ -46,11 +64,21 @@ impl std::convert::TryFrom<PlaylistTrack> for Track {
             &pl_track.album_name,
         )
         .context("Failed to calculate a path to store a playlist track at")?;
+        let url_map = pl_track.audio_url_map;
+        let audio_streams = vec![
+            (AudioQuality::High, url_map.high_quality.audio_url),
+            (AudioQuality::Medium, url_map.medium_quality.audio_url),
+            (AudioQuality::Low, url_map.low_quality.audio_url),
+        ]
+        .into_iter()
+        .filter(|(_, url)| !url.is_empty())
+        .collect();
         let track = Track {
             track_token: pl_track.track_token,
             music_id: pl_track.music_id,
             station_id: pl_track.station_id,
-            audio_stream: pl_track.audio_url_map.high_quality.audio_url,
+            audio_streams,
+            audio_quality: None,
             artist_name: pl_track.artist_name,
             album_name: pl_track.album_name,
             title: pl_track.song_name,
@@ -61,6 +89,7 @@ impl std::convert::TryFrom<PlaylistTrack> for Track {
                 .and_then(|v| v.as_u64())
                 .map(Duration::from_secs)
                 .unwrap_or_default(),
+            track_gain: pl_track.optional.get("trackGain").and_then(track_gain),
             cache_path,
         };
         Ok(track)
@@ -96,6 +125,14 @@ impl std::convert::From<&Track> for mpris_server::Metadata {
 }
 
 impl Track {
+    /// Order the audio streams so that the preferred quality is tried first,
+    /// followed by the others in `AudioQuality::fallback_order()`.
+    pub(crate) fn prefer_audio_quality(&mut self, quality: AudioQuality) {
+        let order = quality.fallback_order();
+        self.audio_streams
+            .sort_by_key(|(q, _)| order.iter().position(|o| o == q));
+    }
+
     pub(crate) fn cached(&self) -> bool {
         // Ensure that the track in the cache is playable, it will be deleted if it isn't
         self.cache_path.exists() && self.get_m4a_decoder().is_ok()
@@ -119,20 +156,48 @@ impl Track {
         }
     }
 
-    pub(crate) async fn download_to_cache(&self, client: &reqwest::Client) -> Result<()> {
+    pub(crate) async fn download_to_cache(&mut self, client: &reqwest::Client) -> Result<()> {
         if self.cached() {
             info!("Ignoring request to download track - valid local copy exists in cache");
+            self.audio_quality = cached_audio_quality(&self.cache_path);
             return Ok(());
         }
 
-        let req_builder = client.get(&self.audio_stream);
+        let mut result = Err(Error::NoAudioStream(self.title.clone()).into());
+        for (quality, url) in self.audio_streams.iter() {
+            debug!("Downloading {quality} quality audio for {}", self.title);
+            result = self
+                .download_stream(client, url, *quality)
+                .await
+                .map(|_| *quality);
+            match &result {
+                Ok(_) => break,
+                Err(e) => warn!(
+                    "Failed to download {quality} quality audio for {}: {e:#}",
+                    self.title
+                ),
+            }
+        }
+        let quality = result?;
+        info!("Cached {quality} quality audio for {}", self.title);
+        self.audio_quality = Some(quality);
+        Ok(())
+    }
+
+    async fn download_stream(
+        &self,
+        client: &reqwest::Client,
+        url: &str,
+        quality: AudioQuality,
+    ) -> Result<()> {
+        let req_builder = client.get(url);
 
         if let Err(e) = download_to_cache(req_builder, &self.cache_path).await {
             error!("Failed to download track to cache: {e:#}");
             self.remove_from_cache();
             Err(e)
         } else {
-            self.tag_cached_file()
+            self.tag_cached_file(quality)
                 .context("Failed to apply metadata tags to playlist track")?;
             // Let's make sure the track is playable before we report success adding it to the
             // cache
@@ -142,7 +207,7 @@ impl Track {
         }
     }
 
-    fn tag_cached_file(&self) -> Result<()> {
+    fn tag_cached_file(&self, quality: AudioQuality) -> Result<()> {
         if !self.cache_path.exists() {
             return Err(Error::TrackNotCached(self.title.clone()).into());
         }
@@ -152,6 +217,7 @@ impl Track {
             &self.title,
             &self.artist_name,
             &self.album_name,
+            quality,
         ) {
             error!("Failed to tag cached file: {e:#}");
             self.remove_from_cache();
@@ -223,7 +289,29 @@ async fn download_to_cache<P: AsRef<Path>>(
     Ok(())
 }
 
-fn tag_cached_file<P: AsRef<Path>>(path: P, title: &str, artist: &str, album: &str) -> Result<()> {
+/// Start of the comment tag recording the quality of a cached track's audio,
+/// since there's no telling from the file itself which stream it came from.
+const AUDIO_QUALITY_COMMENT: &str = "Audio quality: ";
+
+/// The quality of the audio in a cached track, as recorded when it was fetched.
+fn cached_audio_quality<P: AsRef<Path>>(path: P) -> Option<AudioQuality> {
+    let tag = mp4ameta::Tag::read_from_path(path.as_ref())
+        .map_err(|e| debug!("No tags read from {}: {e}", path.as_ref().display()))
+        .ok()?;
+    let name = tag.comment()?.strip_prefix(AUDIO_QUALITY_COMMENT)?;
+    [AudioQuality::Low, AudioQuality::Medium, AudioQuality::High]
+        .iter()
+        .copied()
+        .find(|quality| quality.to_string() == name)
+}
+
+fn tag_cached_file<P: AsRef<Path>>(
+    path: P,
+    title: &str,
+    artist: &str,
+    album: &str,
+    quality: AudioQuality,
+) -> Result<()> {
     let path = path.as_ref();
     debug!("Reading tags from m4a");
     let mut tag = match mp4ameta::Tag::read_from_path(path) {
@@ -255,6 +343,12 @@ fn tag_cached_file<P: AsRef<Path>>(path: P, title: &str, artist: &str, album: &s
         dirty = true;
     }
 
+    let comment = format!("{AUDIO_QUALITY_COMMENT}{quality}");
+    if tag.comment() != Some(comment.as_str()) {
+        tag.set_comment(comment);
+        dirty = true;
+    }
+
     if dirty {
         debug!("Writing tags back to file");
         tag.write_to_path(path).with_context(|| {
@@ -299,8 +393,22 @@ fn sanitize_filename(text: &str) -> String {
         .collect()
 }
 
-fn app_cache_dir() -> Result<PathBuf> {
+pub(crate) fn app_cache_dir() -> Result<PathBuf> {
     Ok(dirs::cache_dir()
         .ok_or(Error::AppDirNotFound)?
         .join(clap::crate_name!()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn implausible_track_gain_is_ignored() {
+        assert_eq!(track_gain(&serde_json::json!("-4.59")), Some(-4.59));
+        assert_eq!(track_gain(&serde_json::json!(2.5)), Some(2.5));
+        assert_eq!(track_gain(&serde_json::json!("90.0")), None);
+        assert_eq!(track_gain(&serde_json::json!("NaN")), None);
+        assert_eq!(track_gain(&serde_json::json!("loud")), None);
+    }
+}